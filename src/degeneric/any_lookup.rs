@@ -0,0 +1,137 @@
+use syn::{parse_quote, Generics, Ident, TraitBoundModifier, TraitItem, Type, TypeParamBound};
+
+use super::field::FieldDecl;
+use super::generics::where_clause_bounds;
+use super::type_tools::{mentioned_ident, mentioned_lifetime};
+
+/// Whether `bound` is `'static`.
+fn is_static(bound: &TypeParamBound) -> bool {
+    matches!(bound, TypeParamBound::Lifetime(lt) if lt.ident == "static")
+}
+
+/// Whether `bound` is `?Sized`.
+fn is_maybe_sized(bound: &TypeParamBound) -> bool {
+    matches!(bound, TypeParamBound::Trait(tb) if matches!(tb.modifier, TraitBoundModifier::Maybe(_)))
+}
+
+/// Types which can't be turned into `&dyn Any`, such as `[u8]`, `str`, `dyn Trait` or a type
+/// parameter from `unsized_params`.
+fn is_unsized(ty: &Type, unsized_params: &[&Ident]) -> bool {
+    match ty {
+        Type::Slice(_) | Type::TraitObject(_) => true,
+        Type::Paren(paren) => is_unsized(&paren.elem, unsized_params),
+        Type::Group(group) => is_unsized(&group.elem, unsized_params),
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .is_some_and(|ident| ident == "str" || unsized_params.contains(&ident)),
+        _ => false,
+    }
+}
+
+/// `get_any` and `get_any_mut` together with their typed wrappers. Only fields whose type is
+/// known to be `'static` and sized can be looked up.
+pub struct AnyLookup<'d> {
+    pub fields: Vec<&'d FieldDecl>,
+}
+
+impl<'d> AnyLookup<'d> {
+    pub fn new(generics: &Generics, fields: impl IntoIterator<Item = &'d FieldDecl>) -> Self {
+        let bounds_of = |ident| {
+            generics
+                .type_params()
+                .find(|tp| &tp.ident == ident)
+                .into_iter()
+                .flat_map(|tp| tp.bounds.iter().cloned())
+                .chain(where_clause_bounds(generics, ident))
+                .collect::<Vec<_>>()
+        };
+        let params: Vec<_> = generics.type_params().map(|tp| &tp.ident).collect();
+        let non_static_params: Vec<_> = params
+            .iter()
+            .copied()
+            .filter(|ident| !bounds_of(ident).iter().any(is_static))
+            .collect();
+        let unsized_params: Vec<_> = params
+            .iter()
+            .copied()
+            .filter(|ident| bounds_of(ident).iter().any(is_maybe_sized))
+            .collect();
+        let fields = fields
+            .into_iter()
+            .filter(|f| f.no_getter.is_none())
+            .filter(|f| mentioned_lifetime(&f.ty).is_none())
+            .filter(|f| mentioned_ident(&f.ty, &non_static_params).is_none())
+            .filter(|f| !is_unsized(&f.ty, &unsized_params))
+            .collect();
+        Self { fields }
+    }
+
+    /// Declarations and implementation of `get_any` and `get`.
+    pub fn methods(&self) -> (Vec<TraitItem>, Vec<TraitItem>) {
+        let tys = self.fields.iter().map(|f| &f.ty);
+        let members = self.fields.iter().map(|f| f.member());
+        (
+            vec![
+                parse_quote! {
+                    /// Borrows the first field of the type identified by `id`.
+                    fn get_any(&self, id: ::core::any::TypeId) -> Option<&dyn ::core::any::Any>;
+                },
+                parse_quote! {
+                    /// Borrows the first field of type `T`.
+                    fn get<T: ::core::any::Any>(&self) -> Option<&T> where Self: Sized {
+                        self.get_any(::core::any::TypeId::of::<T>())
+                            .and_then(|any| any.downcast_ref::<T>())
+                    }
+                },
+            ],
+            vec![parse_quote! {
+                fn get_any(&self, id: ::core::any::TypeId) -> Option<&dyn ::core::any::Any> {
+                    #(
+                        if id == ::core::any::TypeId::of::<#tys>() {
+                            return Some(&self.#members);
+                        }
+                    )*
+                    None
+                }
+            }],
+        )
+    }
+
+    /// Declarations and implementation of `get_any_mut` and `get_mut`.
+    pub fn mut_methods(&self) -> (Vec<TraitItem>, Vec<TraitItem>) {
+        let tys = self.fields.iter().map(|f| &f.ty);
+        let members = self.fields.iter().map(|f| f.member());
+        (
+            vec![
+                parse_quote! {
+                    /// Mutably borrows the first field of the type identified by `id`.
+                    fn get_any_mut(
+                        &mut self,
+                        id: ::core::any::TypeId,
+                    ) -> Option<&mut dyn ::core::any::Any>;
+                },
+                parse_quote! {
+                    /// Mutably borrows the first field of type `T`.
+                    fn get_mut<T: ::core::any::Any>(&mut self) -> Option<&mut T> where Self: Sized {
+                        self.get_any_mut(::core::any::TypeId::of::<T>())
+                            .and_then(|any| any.downcast_mut::<T>())
+                    }
+                },
+            ],
+            vec![parse_quote! {
+                fn get_any_mut(
+                    &mut self,
+                    id: ::core::any::TypeId,
+                ) -> Option<&mut dyn ::core::any::Any> {
+                    #(
+                        if id == ::core::any::TypeId::of::<#tys>() {
+                            return Some(&mut self.#members);
+                        }
+                    )*
+                    None
+                }
+            }],
+        )
+    }
+}
@@ -1,34 +1,52 @@
 use darling::{FromAttributes, FromMeta, Result};
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
+use quote::ToTokens;
 use syn::{
     parse::{Parse, ParseBuffer},
-    Attribute, Ident, Token, Visibility,
+    punctuated::Punctuated,
+    Attribute, Generics, Ident, Token, TypeParamBound, Visibility,
 };
 
+/// Header of the generated trait, e.g. `pub unsafe trait Container<X>: Send where X: Debug`.
 pub struct TraitDecl {
     pub vis: Visibility,
+    pub unsafety: Option<Token![unsafe]>,
     pub trait_kw: Token![trait],
     pub ident: Ident,
-}
-
-impl ToTokens for TraitDecl {
-    fn to_tokens(&self, tokens: &mut TokenStream) {
-        let vis = &self.vis;
-        let trait_kw = &self.trait_kw;
-        let ident = &self.ident;
-        tokens.extend(quote! {
-            #vis #trait_kw #ident
-        });
-    }
+    pub generics: Generics,
+    pub colon_token: Option<Token![:]>,
+    pub supertraits: Punctuated<TypeParamBound, Token![+]>,
 }
 
 impl Parse for TraitDecl {
     fn parse(pb: &ParseBuffer) -> syn::Result<Self> {
+        let vis = pb.parse()?;
+        let unsafety = pb.parse()?;
+        let trait_kw = pb.parse()?;
+        let ident = pb.parse()?;
+        let mut generics: Generics = pb.parse()?;
+        let colon_token: Option<Token![:]> = pb.parse()?;
+
+        let mut supertraits = Punctuated::new();
+        if colon_token.is_some() {
+            while !pb.is_empty() && !pb.peek(Token![where]) {
+                supertraits.push_value(pb.parse()?);
+                if pb.is_empty() || pb.peek(Token![where]) {
+                    break;
+                }
+                supertraits.push_punct(pb.parse()?);
+            }
+        }
+        generics.where_clause = pb.parse()?;
+
         Ok(Self {
-            vis: pb.parse()?,
-            trait_kw: pb.parse()?,
-            ident: pb.parse()?,
+            vis,
+            unsafety,
+            trait_kw,
+            ident,
+            generics,
+            colon_token,
+            supertraits,
         })
     }
 }
@@ -42,9 +60,12 @@ impl FromMeta for TraitDecl {
 #[derive(FromAttributes)]
 #[darling(attributes(degeneric))]
 pub struct DegenericTypeAttrs {
-    #[allow(unused)]
     #[darling(default)]
     pub preserve: Option<()>,
+
+    /// Field transformed by `map_<field>` when several fields use the type parameter.
+    #[darling(default)]
+    pub map: Option<Ident>,
 }
 
 pub struct Attrs(Vec<Attribute>);
@@ -68,3 +89,13 @@ impl ToTokens for Attrs {
         }
     }
 }
+
+/// Attributes of the struct which are copied to the items generated next to the trait: `cfg`,
+/// `allow` and the like, but not the docs.
+pub fn non_doc_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| !attr.path().is_ident("doc"))
+        .cloned()
+        .collect()
+}
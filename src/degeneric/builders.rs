@@ -0,0 +1,237 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+use syn::{Generics, Ident, Type, TypeParam, Visibility, WherePredicate};
+
+use super::field::FieldDecl;
+use super::generics::fresh_ident;
+use super::type_tools::{mentioned_ident, renamed_ident};
+
+/// `HttpClient` becomes `http_client`.
+pub fn snake_case(ident: &Ident) -> String {
+    let mut snake = String::new();
+    for (i, ch) in ident.to_string().chars().enumerate() {
+        if ch.is_uppercase() && i > 0 && !snake.ends_with('_') {
+            snake.push('_');
+        }
+        snake.extend(ch.to_lowercase());
+    }
+    snake
+}
+
+/// All bounds of the struct generics written as where clause predicates.
+fn all_predicates(generics: &Generics) -> Vec<WherePredicate> {
+    generics
+        .type_params()
+        .filter(|tp| !tp.bounds.is_empty())
+        .map(|tp| {
+            let ident = &tp.ident;
+            let bounds = &tp.bounds;
+            syn::parse_quote! { #ident: #bounds }
+        })
+        .chain(
+            generics
+                .where_clause
+                .iter()
+                .flat_map(|wh| wh.predicates.iter().cloned()),
+        )
+        .collect()
+}
+
+/// `PhantomData<T>` markers, which can be recreated for any `T`.
+fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        _ => false,
+    }
+}
+
+/// Inherent methods of the struct which replace the fields using the `param` type parameter with
+/// values of another type, e.g. `with_logger<NewLogger>(self, logger: NewLogger)`.
+pub struct TypeChangingMethods<'d> {
+    pub ident: &'d Ident,
+    pub vis: &'d Visibility,
+    pub generics: &'d Generics,
+    pub fields: Vec<&'d FieldDecl>,
+}
+
+impl<'d> TypeChangingMethods<'d> {
+    /// Fields whose type mentions `param`.
+    fn fields_of(&self, param: &TypeParam) -> Vec<&'d FieldDecl> {
+        self.fields
+            .iter()
+            .copied()
+            .filter(|f| mentioned_ident(&f.ty, &[&param.ident]).is_some())
+            .collect()
+    }
+
+    /// Predicate which bounds another type by `param`, e.g. `A: Fn() -> T` for `T`. That type
+    /// keeps its bounds, so `param` can't be replaced on its own.
+    fn bound_by(&self, param: &TypeParam) -> Option<WherePredicate> {
+        all_predicates(self.generics)
+            .into_iter()
+            .find(|pred| match pred {
+                WherePredicate::Type(pt) => {
+                    mentioned_ident(&pt.bounded_ty, &[&param.ident]).is_none()
+                        && mentioned_ident(&pt.bounds, &[&param.ident]).is_some()
+                }
+                _ => false,
+            })
+    }
+
+    /// The new type parameter and the predicates it has to satisfy in place of `param`.
+    fn replace_param(&self, param: &TypeParam) -> (Ident, Vec<TokenStream>) {
+        let new = fresh_ident(self.generics, &format!("New{}", param.ident));
+        let predicates = all_predicates(self.generics)
+            .into_iter()
+            .filter(|pred| mentioned_ident(pred, &[&param.ident]).is_some())
+            .map(|pred| renamed_ident(pred, &param.ident, &new))
+            .collect();
+        (new, predicates)
+    }
+
+    /// `with_<field>` if only one field uses `param`, otherwise `with_<param>` which takes all
+    /// of them. Nothing is generated if the bounds of another parameter mention `param`.
+    pub fn with(&self, param: &TypeParam) -> Option<TokenStream> {
+        if self.bound_by(param).is_some() {
+            return None;
+        }
+        let fields = self.fields_of(param);
+        let (name, lints) = match fields.as_slice() {
+            [] => return None,
+            [field] => (field.prefixed_name("with"), field.prefixed_name_attrs()),
+            _ => (format_ident!("with_{}", snake_case(&param.ident)), None),
+        };
+        let (new, predicates) = self.replace_param(param);
+        let (_, tys, _) = self.generics.split_for_impl();
+        let ident = self.ident;
+        let vis = self.vis;
+        let new_tys = renamed_ident(&tys, &param.ident, &new);
+        let args = fields.iter().map(|f| {
+            let arg = f.getter_name();
+            let ty = renamed_ident(&f.ty, &param.ident, &new);
+            quote! { #arg: #ty }
+        });
+        let inits = self.fields.iter().map(|f| {
+            let member = f.member();
+            if fields.iter().any(|replaced| replaced.index == f.index) {
+                let arg = f.getter_name();
+                quote! { #member: #arg }
+            } else {
+                quote! { #member: self.#member }
+            }
+        });
+        let doc = format!(
+            "Replaces the values of type `{}` with values of another type.",
+            param.ident
+        );
+
+        Some(quote! {
+            #[doc = #doc]
+            #lints
+            #vis fn #name<#new>(self, #(#args),*) -> #ident #new_tys
+            where
+                #(#predicates,)*
+            {
+                #ident {
+                    #(#inits,)*
+                }
+            }
+        })
+    }
+
+    /// `map_<field>` which transforms the field using `param` with a closure. When several fields
+    /// use `param`, `hint` picks the one to transform and the other ones are passed as arguments
+    /// like in `with_<param>`, except for `PhantomData` markers. Like `with_<field>`, nothing is
+    /// generated if the bounds of another parameter mention `param`.
+    pub fn map(&self, param: &TypeParam, hint: Option<&Ident>) -> Option<TokenStream> {
+        match (self.bound_by(param), hint) {
+            (Some(pred), Some(hint)) => abort!(
+                hint,
+                "the type parameter `{}` can't be mapped, the predicate `{}` ties it to another type parameter",
+                param.ident,
+                pred.to_token_stream()
+            ),
+            (Some(_), None) => return None,
+            (None, _) => {}
+        }
+        let fields = self.fields_of(param);
+        let mapped = match (fields.as_slice(), hint) {
+            ([], None) => return None,
+            (_, Some(hint)) => match fields
+                .iter()
+                .find(|f| &f.getter_name() == hint || f.ident.as_ref() == Some(hint))
+            {
+                Some(field) => *field,
+                None => abort!(
+                    hint,
+                    "there's no field named `{}` using the type parameter `{}`",
+                    hint,
+                    param.ident
+                ),
+            },
+            ([field], None) => *field,
+            (_, None) => abort!(
+                param,
+                "several fields use the type parameter `{}`, degeneric doesn't know which one to map", param.ident;
+                help = "pick the field with `#[degeneric(map = \"field\")]` on the type parameter"
+            ),
+        };
+        let name = mapped.prefixed_name("map");
+        let lints = mapped.prefixed_name_attrs();
+        let (new, mut predicates) = self.replace_param(param);
+        let func = fresh_ident(self.generics, "F");
+        let (_, tys, _) = self.generics.split_for_impl();
+        let ident = self.ident;
+        let vis = self.vis;
+        let new_tys = renamed_ident(&tys, &param.ident, &new);
+
+        let mapped_ty = &mapped.ty;
+        let new_mapped_ty = renamed_ident(mapped_ty, &param.ident, &new);
+        predicates.push(quote! { #func: FnOnce(#mapped_ty) -> #new_mapped_ty });
+        let passed: Vec<_> = fields
+            .iter()
+            .filter(|f| f.index != mapped.index && !is_phantom_data(&f.ty))
+            .collect();
+        let args = passed.iter().map(|f| {
+            let arg = f.getter_name();
+            let ty = renamed_ident(&f.ty, &param.ident, &new);
+            quote! { #arg: #ty }
+        });
+
+        let inits = self.fields.iter().map(|f| {
+            let member = f.member();
+            if f.index == mapped.index {
+                quote! { #member: __degeneric_f(self.#member) }
+            } else if passed.iter().any(|replaced| replaced.index == f.index) {
+                let arg = f.getter_name();
+                quote! { #member: #arg }
+            } else if fields.iter().any(|replaced| replaced.index == f.index) {
+                quote! { #member: ::core::marker::PhantomData }
+            } else {
+                quote! { #member: self.#member }
+            }
+        });
+        let doc = format!(
+            "Transforms the field `{}` into a value of another type.",
+            mapped.getter_name()
+        );
+
+        Some(quote! {
+            #[doc = #doc]
+            #lints
+            #vis fn #name<#new, #func>(self, __degeneric_f: #func, #(#args),*) -> #ident #new_tys
+            where
+                #(#predicates,)*
+            {
+                #ident {
+                    #(#inits,)*
+                }
+            }
+        })
+    }
+}
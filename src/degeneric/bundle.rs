@@ -0,0 +1,162 @@
+use darling::ast::NestedMeta;
+use darling::{FromAttributes, FromMeta};
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, GenericParam, Ident, Path, Token, Type,
+    WherePredicate,
+};
+
+use super::attribute::DegenericTypeAttrs;
+use super::generics::*;
+use super::type_tools::{predicate_to_associated_ty, to_associated_ty};
+
+#[derive(FromMeta)]
+struct BundleArgs {
+    types: Ident,
+}
+
+/// Whether the struct is also processed by `#[derive(Degeneric)]`, which is the only place where
+/// `#[degeneric(...)]` attributes may stay.
+fn derives_degeneric(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .flat_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .unwrap_or_default()
+        })
+        .any(|path| {
+            path.segments
+                .last()
+                .is_some_and(|seg| seg.ident == "Degeneric")
+        })
+}
+
+/// Rewrites the struct to take a single type parameter implementing the generated types trait
+/// instead of one type parameter per dependency.
+pub fn process_bundle(args: TokenStream, input: DeriveInput) -> syn::Result<TokenStream> {
+    let args = NestedMeta::parse_meta_list(args)?;
+    let args = BundleArgs::from_list(&args).map_err(|err| syn::Error::new(input.span(), err))?;
+    let mut input = input;
+
+    let types_ident = &args.types;
+    let marker_ident = format_ident!("{}Of", types_ident);
+    let struct_ident = &input.ident;
+    let vis = &input.vis;
+    let generics = input.generics.clone();
+    let bare_generics = without_helper_attrs(&generics);
+    let (impl_generics, _, where_clause) = bare_generics.split_for_impl();
+
+    let mut associated_type_params = vec![];
+    for tp in generics.type_params() {
+        let attrs = pme_unwrap!(
+            DegenericTypeAttrs::from_attributes(&tp.attrs),
+            tp.span(),
+            "failed to read attributes of the type parameter: {err}"
+        );
+        if attrs.preserve.is_none() {
+            associated_type_params.push(tp);
+        }
+    }
+    if associated_type_params.is_empty() {
+        abort!(
+            generics.span(),
+            "there are no type parameters to collect into `{}`",
+            types_ident
+        );
+    }
+
+    let subst = associated_substitution(&generics, associated_type_params.iter().copied());
+    let types_generics = TraitGenerics::from((&generics, &subst, &vec![]));
+    let (_, types_args, types_where_clause) = types_generics.0.split_for_impl();
+    let types_path: Path = parse_quote! { #types_ident #types_args };
+    let associated_types = associated_type_params
+        .iter()
+        .map(|tp| AssociatedType::from((*tp, &generics, &subst)));
+    let associated_types_impl = associated_type_params.iter().map(|tp| {
+        let ident = &tp.ident;
+        quote! { type #ident = #ident; }
+    });
+    let marker_params: Vec<_> = associated_type_params.iter().map(|tp| &tp.ident).collect();
+
+    // the struct keeps lifetimes, preserved and const parameters and gets the types parameter
+    let ty_ident = fresh_ident(&generics, "Ty");
+    let ty_subst = subst.with_owner(parse_quote! { #ty_ident }, None);
+    let keep_degeneric_attrs = derives_degeneric(&input.attrs);
+    let mut struct_generics = if keep_degeneric_attrs {
+        generics.clone()
+    } else {
+        bare_generics.clone()
+    };
+    struct_generics.params = struct_generics
+        .params
+        .into_iter()
+        .filter(|param| !matches!(param, GenericParam::Type(tp) if subst.contains(&tp.ident)))
+        .collect();
+    struct_generics
+        .params
+        .push(parse_quote! { #ty_ident: #types_path });
+    if let Some(wh) = struct_generics.where_clause.as_mut() {
+        wh.predicates = wh
+            .predicates
+            .iter()
+            .filter(|pred| match pred {
+                WherePredicate::Type(pt) => !matches!(
+                    &pt.bounded_ty,
+                    Type::Path(path) if path.path.get_ident().is_some_and(|id| subst.contains(id))
+                ),
+                _ => true,
+            })
+            .cloned()
+            .map(|pred| predicate_to_associated_ty(pred, &ty_subst))
+            .collect();
+    }
+    let struct_generics = generics_to_associated_ty(&struct_generics, &ty_subst);
+    input.generics = struct_generics;
+
+    match &mut input.data {
+        Data::Struct(data) => {
+            for field in data.fields.iter_mut() {
+                field.ty = to_associated_ty(field.ty.clone(), &ty_subst);
+            }
+        }
+        _ => abort!(input.span(), "bundle can only be used on structs"),
+    }
+
+    let cfg_attrs: Vec<_> = input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("cfg_attr"))
+        .collect();
+    let types_doc = format!("Types of the dependencies of [`{}`].", struct_ident);
+    let marker_doc = format!(
+        "Implements [`{}`] with the types given as parameters.",
+        types_ident
+    );
+
+    Ok(quote! {
+        #input
+
+        #(#cfg_attrs)*
+        #[doc = #types_doc]
+        #vis trait #types_ident #types_generics #types_where_clause {
+            #(#associated_types)*
+        }
+
+        #(#cfg_attrs)*
+        #[doc = #marker_doc]
+        #vis struct #marker_ident<#(#marker_params),*>(
+            ::core::marker::PhantomData<fn() -> (#(#marker_params,)*)>,
+        );
+
+        #(#cfg_attrs)*
+        #[automatically_derived]
+        impl #impl_generics #types_path for #marker_ident<#(#marker_params),*> #where_clause {
+            #(#associated_types_impl)*
+        }
+    })
+}
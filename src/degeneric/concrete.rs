@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, Attribute, ConstParam, GenericParam, Generics, Ident, Path, TraitItem, Type,
+    Visibility,
+};
+
+use super::generics::fresh_ident_except;
+use super::type_tools::Substitution;
+
+/// Alias which recovers the struct from an implementor of the trait, e.g.
+/// `type ContainerOf<C> = Container<<C as ContainerTrait>::Logger>`.
+pub struct ConcreteAlias<'d> {
+    pub attrs: &'d [Attribute],
+    pub vis: &'d Visibility,
+    pub ident: &'d Ident,
+    pub target: &'d Ident,
+    pub target_generics: &'d Generics,
+    pub trait_generics: &'d Generics,
+}
+
+fn param_arg(param: &GenericParam) -> TokenStream {
+    match param {
+        GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+        GenericParam::Type(tp) => tp.ident.to_token_stream(),
+        GenericParam::Const(cp) => cp.ident.to_token_stream(),
+    }
+}
+
+impl<'d> ConcreteAlias<'d> {
+    /// The struct can only be recovered from associated types, not from associated consts.
+    pub fn check(&self, associated_const_params: &[&ConstParam], has_associated_types: bool) {
+        if let Some(cp) = associated_const_params.first() {
+            abort!(
+                cp,
+                "the concrete type can't be recovered from the associated const `{}`", cp.ident;
+                help = "mark it with `#[degeneric(preserve)]` to keep it a generic parameter of the trait"
+            );
+        }
+        if !has_associated_types {
+            abort!(
+                self.ident,
+                "there are no associated types to recover the concrete type from"
+            );
+        }
+    }
+
+    /// The lifetimes and the other parameters of the trait, which are in front of and behind the
+    /// container parameter of the alias.
+    fn trait_params(&self) -> (Vec<&GenericParam>, Vec<&GenericParam>) {
+        self.trait_generics
+            .params
+            .iter()
+            .partition(|param| matches!(param, GenericParam::Lifetime(_)))
+    }
+
+    /// Declares the alias, which takes the associated types of the `trait_path` trait from its
+    /// container parameter.
+    pub fn emit(&self, trait_path: &Path, subst: &Substitution) -> TokenStream {
+        let trait_name = &trait_path
+            .segments
+            .last()
+            .expect("the trait has a name")
+            .ident;
+        // the alias refers to the struct and the trait by their bare names
+        let container = fresh_ident_except(self.trait_generics, "C", &[self.target, trait_name]);
+        let (trait_lifetimes, trait_others) = self.trait_params();
+        let lifetime_args = trait_lifetimes.iter().map(|p| param_arg(p));
+        let other_params = trait_others.iter().map(|param| match param {
+            GenericParam::Const(cp) => {
+                let ident = &cp.ident;
+                let ty = &cp.ty;
+                quote! { const #ident: #ty }
+            }
+            param => param_arg(param),
+        });
+        let container_subst =
+            subst.with_owner(parse_quote! { #container }, Some(trait_path.clone()));
+        let target_args = self.target_generics.params.iter().map(|param| match param {
+            GenericParam::Type(tp) => match container_subst.replacement(&tp.ident) {
+                Some(ty) => ty.to_token_stream(),
+                None => tp.ident.to_token_stream(),
+            },
+            param => param_arg(param),
+        });
+        let attrs = &self.attrs;
+        let vis = self.vis;
+        let ident = self.ident;
+        let target = self.target;
+        let doc = format!(
+            "The `{}` struct of a [`{}`] implementor.",
+            target, trait_name
+        );
+
+        quote! {
+            #(#attrs)*
+            #[doc = #doc]
+            #vis type #ident<#(#lifetime_args,)* #container, #(#other_params),*> =
+                #target<#(#target_args),*>;
+        }
+    }
+
+    /// The alias instantiated for `Self`.
+    fn self_ty(&self) -> Type {
+        let (trait_lifetimes, trait_others) = self.trait_params();
+        let lifetime_args = trait_lifetimes.iter().map(|p| param_arg(p));
+        let other_args = trait_others.iter().map(|p| param_arg(p));
+        let ident = self.ident;
+        parse_quote! { #ident<#(#lifetime_args,)* Self, #(#other_args),*> }
+    }
+
+    /// Declaration and implementation of `as_concrete`.
+    pub fn methods(&self) -> (Vec<TraitItem>, Vec<TraitItem>) {
+        let concrete_ty = self.self_ty();
+        (
+            vec![parse_quote! {
+                /// Borrows the container as the concrete struct.
+                fn as_concrete(&self) -> &#concrete_ty;
+            }],
+            vec![parse_quote! {
+                fn as_concrete(&self) -> &#concrete_ty {
+                    self
+                }
+            }],
+        )
+    }
+
+    /// Declaration and implementation of `into_concrete`.
+    pub fn mut_methods(&self) -> (Vec<TraitItem>, Vec<TraitItem>) {
+        let concrete_ty = self.self_ty();
+        (
+            vec![parse_quote! {
+                /// Turns the container into the concrete struct.
+                fn into_concrete(self) -> #concrete_ty where Self: Sized;
+            }],
+            vec![parse_quote! {
+                fn into_concrete(self) -> #concrete_ty where Self: Sized {
+                    self
+                }
+            }],
+        )
+    }
+}
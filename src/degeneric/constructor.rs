@@ -0,0 +1,55 @@
+use proc_macro_error::abort;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_quote, Ident, TraitItem};
+
+use super::field::FieldDecl;
+use super::type_tools::{mentioned_ident, to_associated_ty, Substitution};
+
+/// Declaration and implementation of `fn new(...) -> Self`, which takes all fields as arguments
+/// except those marked with `#[degeneric(default)]`.
+pub fn constructor(
+    fields: &[&FieldDecl],
+    associated_consts_idents: &[&Ident],
+    subst: &Substitution,
+) -> (Vec<TraitItem>, Vec<TraitItem>) {
+    let args: Vec<_> = fields
+        .iter()
+        .filter(|f| f.default.is_none())
+        .map(|f| {
+            if let Some(const_ident) = mentioned_ident(&f.ty, associated_consts_idents) {
+                abort!(
+                    f.ty.span(),
+                    "const parameter `{}` can't be used in arguments of the generated constructor", const_ident;
+                    help = "fill the field in with `#[degeneric(default)]`"
+                );
+            }
+            let arg = f.getter_name();
+            let ty = to_associated_ty(f.ty.clone(), subst);
+            quote! { #arg: #ty }
+        })
+        .collect();
+    let inits = fields.iter().map(|f| {
+        let member = f.member();
+        if f.default.is_some() {
+            quote! { #member: Default::default() }
+        } else {
+            let arg = f.getter_name();
+            quote! { #member: #arg }
+        }
+    });
+
+    (
+        vec![parse_quote! {
+            /// Creates the container from its fields.
+            fn new(#(#args),*) -> Self where Self: Sized;
+        }],
+        vec![parse_quote! {
+            fn new(#(#args),*) -> Self where Self: Sized {
+                Self {
+                    #(#inits,)*
+                }
+            }
+        }],
+    )
+}
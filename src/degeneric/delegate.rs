@@ -0,0 +1,89 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, GenericParam, Generics, Ident, Path, Token, TraitItem, Type,
+    TypeParamBound,
+};
+
+use super::forward;
+use super::type_tools::{replaced_ident, Substitution};
+
+/// Trait implemented by the delegation macro together with its items.
+pub struct DelegatedTrait<'t> {
+    pub path: &'t Path,
+    pub items: Vec<TraitItem>,
+}
+
+/// `macro_rules!` which implements the traits for a wrapper type by forwarding everything to a
+/// field, e.g. `delegate_container_trait!([C] Traced<C> => self.0: C)`.
+pub fn emit_delegate_macro(
+    attrs: &[Attribute],
+    name: &Ident,
+    traits: &[DelegatedTrait],
+    subst: &Substitution,
+    trait_generics_with: impl Fn(&Substitution) -> Generics,
+    supertraits_with: impl Fn(&Substitution) -> Punctuated<TypeParamBound, Token![+]>,
+) -> TokenStream {
+    // the placeholders become the macro arguments
+    let inner_ident = format_ident!("__DegenericInner");
+    let wrapper_ident = format_ident!("__DegenericWrapper");
+    let field_ident = format_ident!("__degeneric_field");
+    let inner: Type = parse_quote! { #inner_ident };
+
+    let main_path = traits[0].path;
+    let inner_subst = subst.with_owner(inner.clone(), Some(main_path.clone()));
+    let generics = trait_generics_with(&inner_subst);
+    let (lifetimes, others): (Vec<_>, Vec<_>) = generics
+        .params
+        .iter()
+        .partition(|param| matches!(param, GenericParam::Lifetime(_)));
+    let mut predicates: Vec<TokenStream> = generics
+        .where_clause
+        .iter()
+        .flat_map(|wh| &wh.predicates)
+        .map(|pred| quote! { #pred })
+        .collect();
+    let supertraits = supertraits_with(&inner_subst);
+    if !supertraits.is_empty() {
+        predicates.push(quote! { #wrapper_ident: #supertraits });
+    }
+
+    let impls = traits.iter().map(|delegated| {
+        let path = delegated.path;
+        let forwarded = forward::forward_items(&delegated.items, &inner, path, |rcv| {
+            match (&rcv.reference, &rcv.mutability) {
+                (Some(_), None) => quote! { &self.#field_ident },
+                (Some(_), Some(_)) => quote! { &mut self.#field_ident },
+                (None, _) => quote! { self.#field_ident },
+            }
+        });
+        quote! {
+            #[automatically_derived]
+            impl<#(#lifetimes,)* $($($generics)+,)? #(#others),*> #path for #wrapper_ident
+            where
+                #inner: #path,
+                #(#predicates,)*
+            {
+                #(#forwarded)*
+            }
+        }
+    });
+    let impls = quote! { #(#impls)* };
+    let impls = replaced_ident(impls, &inner_ident, &quote! { $inner });
+    let impls = replaced_ident(impls, &wrapper_ident, &quote! { $wrapper });
+    let impls = replaced_ident(impls, &field_ident, &quote! { $field });
+
+    quote! {
+        #(#attrs)*
+        #[allow(unused_macros)]
+        macro_rules! #name {
+            ([$($($generics:tt)+)?] $wrapper:ty => self.$field:tt: $inner:ty) => {
+                #impls
+            };
+            ($wrapper:ty => self.$field:tt: $inner:ty) => {
+                #name!([] $wrapper => self.$field: $inner);
+            };
+        }
+    }
+}
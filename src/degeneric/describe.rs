@@ -0,0 +1,34 @@
+use syn::{parse_quote, Ident, TraitItem};
+
+use super::field::FieldDecl;
+use super::type_tools::{to_associated_ty, Substitution};
+
+/// Provided `describe_dependencies`, which lists the associated types and the fields with a
+/// getter together with the names of their concrete types.
+pub fn describe_dependencies(
+    associated_idents: &[&Ident],
+    fields: &[&FieldDecl],
+    subst: &Substitution,
+) -> TraitItem {
+    let names = associated_idents
+        .iter()
+        .map(|ident| ident.to_string())
+        .chain(fields.iter().map(|f| f.getter_name().to_string()));
+    let tys = associated_idents
+        .iter()
+        .flat_map(|ident| subst.replacement(ident))
+        .chain(fields.iter().map(|f| to_associated_ty(f.ty.clone(), subst)));
+
+    parse_quote! {
+        /// Names of the associated types and fields together with the names of their
+        /// concrete types.
+        fn describe_dependencies() -> ::std::vec::Vec<(&'static str, &'static str)>
+        where
+            Self: Sized,
+        {
+            ::std::vec![
+                #((#names, ::core::any::type_name::<#tys>()),)*
+            ]
+        }
+    }
+}
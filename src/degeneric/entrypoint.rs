@@ -1,12 +1,32 @@
-use super::type_tools::can_be_made_mutable;
+use super::any_lookup::AnyLookup;
+use super::builders::{snake_case, TypeChangingMethods};
+use super::concrete::ConcreteAlias;
+use super::constructor::constructor;
+use super::delegate::{emit_delegate_macro, DelegatedTrait};
+use super::describe::describe_dependencies;
+use super::field_traits::FieldTrait;
+use super::flatten::emit_flattenable;
+use super::groups::{group_supertraits, GroupTrait, TraitGroup};
+use super::into_parts::into_parts;
+use super::parts::PartsStruct;
+use super::profile::Profile;
+use super::split_mut::emit_pointer_impls;
+use super::type_tools::{
+    bound_to_associated_ty, can_be_made_mutable, mentioned_ident, mentioned_idents,
+    predicate_to_associated_ty, Substitution,
+};
 use darling::FromAttributes;
 use darling::{FromDeriveInput, Result, ToTokens};
 use proc_macro2::TokenStream;
+use proc_macro_error::abort;
 
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::spanned::Spanned;
 
-use syn::{Attribute, DeriveInput, Generics, Ident};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, DeriveInput, Generics, Ident, Path, Token, TraitItem, Visibility,
+};
 
 use super::attribute::*;
 use super::field::*;
@@ -15,11 +35,13 @@ use super::generics::*;
 #[derive(FromDeriveInput)]
 #[darling(
     attributes(degeneric),
-    supports(struct_named),
-    forward_attrs(allow, cfg, cfg_attr, doc)
+    supports(struct_named, struct_tuple),
+    forward_attrs(allow, cfg, cfg_attr, doc),
+    and_then = Self::number_fields
 )]
 struct Degeneric {
     ident: Ident,
+    vis: Visibility,
     generics: Generics,
 
     trait_decl: Option<TraitDecl>,
@@ -38,21 +60,83 @@ struct Degeneric {
     #[darling(default)]
     haz: Option<()>,
 
+    split_mut: Option<Ident>,
+
+    mut_parts: Option<Ident>,
+
+    view: Option<Ident>,
+
+    #[darling(default)]
+    into_parts: Option<()>,
+
+    #[darling(default)]
+    setters: Option<()>,
+
+    #[darling(default)]
+    with: Option<()>,
+
+    #[darling(default)]
+    map: Option<()>,
+
+    #[darling(default)]
+    constructor: Option<()>,
+
+    concrete: Option<Ident>,
+
+    #[darling(multiple)]
+    profile: Vec<Profile>,
+
+    #[darling(multiple)]
+    trait_group: Vec<TraitGroup>,
+
+    #[darling(default)]
+    group_supertraits: Option<()>,
+
+    #[darling(default)]
+    field_traits: Option<()>,
+
+    #[darling(default)]
+    flattenable: Option<()>,
+
+    #[darling(default)]
+    delegate_macro: Option<()>,
+
+    #[darling(default)]
+    any_lookup: Option<()>,
+
+    #[darling(default)]
+    describe: Option<()>,
+
     data: darling::ast::Data<darling::util::Ignored, FieldDecl>,
 }
 
+impl Degeneric {
+    fn fields(&self) -> impl Iterator<Item = &FieldDecl> {
+        self.data.as_ref().take_struct().into_iter().flatten()
+    }
+
+    fn number_fields(mut self) -> Result<Self> {
+        if let darling::ast::Data::Struct(fields) = &mut self.data {
+            for (index, field) in fields.fields.iter_mut().enumerate() {
+                field.index = index;
+            }
+        }
+        Ok(self)
+    }
+}
+
 impl ToTokens for Degeneric {
     fn to_tokens(&self, ts: &mut TokenStream) {
         let trait_decl_attr = &self.trait_decl_attr;
         let trait_impl_attr = &self.trait_impl_attr;
         let attrs = &self.attrs;
+        let cfg_attrs = non_doc_attrs(attrs);
         let generics = &self.generics;
-        let trait_generics = TraitGenerics::from(generics);
         let ident = &self.ident;
-        let (impl_generics, tys, where_clause) = generics.split_for_impl();
-        let (_, trait_ty_generics, _) = trait_generics.0.split_for_impl();
+        let bare_generics = without_helper_attrs(generics);
+        let (impl_generics, tys, where_clause) = bare_generics.split_for_impl();
 
-        let associated_types_idents: Result<Vec<_>> = self
+        let associated_type_params: Result<Vec<_>> = self
             .generics
             .type_params()
             .map(|tp| Ok((tp, DegenericTypeAttrs::from_attributes(&tp.attrs)?)))
@@ -62,21 +146,271 @@ impl ToTokens for Degeneric {
             })
             .collect();
 
-        let associated_types_idents = pme_unwrap!(
-            associated_types_idents,
+        let associated_type_params = pme_unwrap!(
+            associated_type_params,
             generics.span(),
             "failed to get associated types idents: {err}"
         );
 
-        let associated_types_idents: Vec<_> = associated_types_idents
-            .into_iter()
+        let subst =
+            associated_substitution(generics, associated_type_params.iter().map(|(tp, _)| *tp));
+
+        let associated_const_params: Result<Vec<_>> = self
+            .generics
+            .const_params()
+            .map(|cp| Ok((cp, DegenericTypeAttrs::from_attributes(&cp.attrs)?)))
+            .filter(|res| match res {
+                Ok((_, attrs)) => attrs.preserve.is_none(),
+                _ => true,
+            })
+            .collect();
+
+        let associated_const_params = pme_unwrap!(
+            associated_const_params,
+            generics.span(),
+            "failed to get associated consts idents: {err}"
+        );
+
+        let associated_consts: Vec<_> = associated_const_params
+            .iter()
+            .map(|(cp, _)| AssociatedConst::from(*cp))
+            .collect();
+
+        let associated_consts_idents: Vec<_> =
+            associated_consts.iter().map(|ac| &ac.0.ident).collect();
+
+        let associated_consts_impl: Vec<_> = associated_consts
+            .iter()
+            .map(|ac| {
+                let name = &ac.0.ident;
+                let ty = &ac.0.ty;
+                quote! {
+                    const #name: #ty = #name;
+                }
+            })
+            .collect();
+
+        let associated_idents: Vec<_> = associated_type_params
+            .iter()
             .map(|(tp, _)| &tp.ident)
             .collect();
+        for f in self.fields() {
+            for group in &f.group {
+                if !self.trait_group.iter().any(|tg| &tg.name == group) {
+                    abort!(
+                        group,
+                        "there's no trait group named `{}`", group;
+                        help = "declare it with `#[degeneric(trait_group(name = \"{}\"))]` on the struct", group
+                    );
+                }
+            }
+        }
+        let group_traits: Vec<_> = self
+            .trait_group
+            .iter()
+            .map(|tg| {
+                let fields: Vec<_> = self
+                    .fields()
+                    .filter(|f| f.no_getter.is_none())
+                    .filter(|f| f.group.contains(&tg.name))
+                    .collect();
+                let seeds = fields
+                    .iter()
+                    .flat_map(|f| mentioned_idents(&f.ty, &associated_idents))
+                    .collect();
+                let params = required_params(generics, &associated_idents, seeds);
+                let mut group_generics =
+                    TraitGenerics::from((generics, &subst, &associated_consts_idents)).0;
+                let predicates: Punctuated<_, Token![,]> = compound_predicates(generics)
+                    .filter(|pred| {
+                        mentioned_idents(pred, &associated_idents)
+                            .iter()
+                            .all(|ident| params.contains(ident))
+                    })
+                    .map(|pred| predicate_to_associated_ty(pred.clone(), &subst))
+                    .collect();
+                group_generics.where_clause = match predicates.len() {
+                    0 => None,
+                    _ => Some(parse_quote! { where #predicates }),
+                };
+                GroupTrait {
+                    attrs: cfg_attrs.clone(),
+                    vis: tg.vis.clone().unwrap_or_else(|| self.vis.clone()),
+                    name: &tg.name,
+                    generics: group_generics,
+                    associated_types: associated_type_params
+                        .iter()
+                        .filter(|(tp, _)| params.contains(&&tp.ident))
+                        .map(|(tp, _)| AssociatedType::from((*tp, generics, &subst)))
+                        .collect(),
+                    params,
+                    fields,
+                }
+            })
+            .collect();
+        for group in &group_traits {
+            ts.extend(group.emit(&subst, ident, &impl_generics, &tys, where_clause));
+        }
 
-        let associated_types: Vec<_> = self
-            .generics
-            .type_params()
-            .map(|tp| AssociatedType::from((tp, generics, &associated_types_idents)))
+        // with groups as supertraits, the main trait takes the associated types from the groups
+        let group_supertraits = match (&self.group_supertraits, &self.split_mut) {
+            (Some(_), Some(split_mut)) => abort!(
+                split_mut,
+                "`group_supertraits` can't be combined with `split_mut`"
+            ),
+            (Some(_), None) => group_supertraits(&group_traits),
+            (None, _) => vec![],
+        };
+        let mut subst = subst;
+        let mut grouped_params = vec![];
+        let mut extracted_fields = vec![];
+        if self.group_supertraits.is_some() {
+            for group in &group_traits {
+                for param in &group.params {
+                    if !grouped_params.contains(param) {
+                        subst = subst.with_qualifier(param, group.path());
+                        grouped_params.push(*param);
+                    }
+                }
+                extracted_fields.extend(group.fields.iter().map(|f| f.index));
+            }
+        }
+
+        // with field traits, the main trait takes the getters from a `HasX` trait per field
+        let field_traits: Vec<_> = match &self.field_traits {
+            Some(_) => self
+                .fields()
+                .filter(|f| f.no_getter.is_none())
+                .map(|f| FieldTrait::new(f, generics, &subst))
+                .collect(),
+            None => vec![],
+        };
+        if self.field_traits.is_none() {
+            if let Some(path) = self.fields().find_map(|f| f.field_trait.as_ref()) {
+                abort!(path, "`field_trait` needs `field_traits` on the struct");
+            }
+        }
+        if let (Some(_), Some(split_mut)) = (&self.field_traits, &self.split_mut) {
+            abort!(
+                split_mut,
+                "`field_traits` can't be combined with `split_mut`"
+            );
+        }
+        if let (Some(_), Some(_), Some(decl)) = (
+            &self.field_traits,
+            &self.group_supertraits,
+            &self.trait_decl,
+        ) {
+            abort!(
+                decl.ident,
+                "`field_traits` can't be combined with `group_supertraits`";
+                help = "both would provide the getters of the main trait"
+            );
+        }
+        for field_trait in &field_traits {
+            ts.extend(field_trait.emit(
+                &cfg_attrs,
+                &self.vis,
+                ident,
+                &impl_generics,
+                &tys,
+                where_clause,
+            ));
+            extracted_fields.push(field_trait.field.index);
+        }
+        // flattened fields make the struct implement the traits of the field types
+        let flattened: Vec<_> = self
+            .fields()
+            .filter_map(|f| f.flatten.as_ref().map(|flatten| (f, flatten)))
+            .collect();
+        if let (Some((f, _)), Some(split_mut)) = (flattened.first(), &self.split_mut) {
+            abort!(
+                f.ty,
+                "fields can't be flattened into a trait generated with `split_mut`";
+                help = "`split_mut` is set here: {}", split_mut
+            );
+        }
+        for (f, flatten) in &flattened {
+            ts.extend(flatten.emit(f, ident, &impl_generics, &tys, where_clause));
+        }
+        if let Some(decl) = self
+            .trait_decl
+            .as_ref()
+            .filter(|_| self.flattenable.is_some())
+        {
+            let unsupported = [
+                ("split_mut", self.split_mut.is_some()),
+                ("mut_parts", self.mut_parts.is_some()),
+                ("into_parts", self.into_parts.is_some()),
+                ("constructor", self.constructor.is_some()),
+                ("concrete", self.concrete.is_some()),
+                ("field_traits", self.field_traits.is_some()),
+                ("group_supertraits", self.group_supertraits.is_some()),
+            ];
+            if let Some((option, _)) = unsupported.iter().find(|(_, set)| *set) {
+                abort!(
+                    decl.ident,
+                    "`flattenable` can't be combined with `{}`", option;
+                    help = "only methods borrowing the container can be forwarded to a flattened field"
+                );
+            }
+        }
+
+        if let Some(decl) = self
+            .trait_decl
+            .as_ref()
+            .filter(|_| self.delegate_macro.is_some())
+        {
+            let unsupported = [
+                ("mut_parts", self.mut_parts.is_some()),
+                ("constructor", self.constructor.is_some()),
+                ("concrete", self.concrete.is_some()),
+                ("field_traits", self.field_traits.is_some()),
+                ("group_supertraits", self.group_supertraits.is_some()),
+                ("flatten", !flattened.is_empty()),
+            ];
+            if let Some((option, _)) = unsupported.iter().find(|(_, set)| *set) {
+                abort!(
+                    decl.ident,
+                    "`delegate_macro` can't be combined with `{}`", option;
+                    help = "the wrapper type can't provide the items generated by `{}`", option
+                );
+            }
+        }
+
+        // `Self::Logger` would be ambiguous next to `HasLogger::Logger` or an associated type of
+        // a flattened trait
+        if let Some(decl) = self.trait_decl.as_ref() {
+            let main_generics = merge_generics(
+                &TraitGenerics::from((generics, &subst, &associated_consts_idents)).0,
+                &decl.generics,
+            );
+            let (_, main_tys, _) = main_generics.split_for_impl();
+            let trait_name = &decl.ident;
+            let main_path: Path = parse_quote! { #trait_name #main_tys };
+            for param in &associated_idents {
+                if !flattened.is_empty() || field_traits.iter().any(|ft| &ft.associated == *param) {
+                    subst = subst.with_qualifier(param, main_path.clone());
+                }
+            }
+        }
+        let subst = subst;
+        let extra_supertraits: Vec<_> = field_traits
+            .iter()
+            .map(|ft| ft.supertrait(&subst))
+            .chain(
+                flattened
+                    .iter()
+                    .map(|(f, flatten)| flatten.supertrait(f, &subst)),
+            )
+            .collect();
+
+        let trait_generics = TraitGenerics::from((generics, &subst, &associated_consts_idents));
+
+        let associated_types: Vec<_> = associated_type_params
+            .iter()
+            .filter(|(tp, _)| !grouped_params.contains(&&tp.ident))
+            .map(|(tp, _)| AssociatedType::from((*tp, generics, &subst)))
             .collect();
 
         let dynamize = if self.dynamize.is_some() {
@@ -95,74 +429,92 @@ impl ToTokens for Degeneric {
             })
             .collect();
 
-        let associated_types_idents: Vec<_> =
-            associated_types.iter().map(|ty| &ty.0.ident).collect();
+        let const_usages = associated_types
+            .iter()
+            .map(|at| {
+                (
+                    at.0.span(),
+                    mentioned_ident(&at.0.bounds, &associated_consts_idents),
+                )
+            })
+            .chain(self.fields().filter(|f| f.no_getter.is_none()).map(|f| {
+                (
+                    f.ty.span(),
+                    mentioned_ident(&f.ty, &associated_consts_idents),
+                )
+            }))
+            .chain(trait_generics.0.where_clause.iter().map(|wh| {
+                (
+                    generics.span(),
+                    mentioned_ident(wh, &associated_consts_idents),
+                )
+            }))
+            .chain(self.trait_decl.iter().map(|decl| {
+                let decl_supertraits = &decl.supertraits;
+                let decl_where_clause = &decl.generics.where_clause;
+                (
+                    decl.ident.span(),
+                    mentioned_ident(
+                        quote! { #decl_supertraits #decl_where_clause },
+                        &associated_consts_idents,
+                    ),
+                )
+            }));
+        for (span, usage) in const_usages {
+            if let Some(const_ident) = usage {
+                abort!(
+                    span,
+                    "const parameter `{}` can't be used in types of the generated trait", const_ident;
+                    help = "mark it with `#[degeneric(preserve)]` to keep it a generic parameter of the trait";
+                    help = "or skip generating the getter with `#[degeneric(no_getter)]`"
+                );
+            }
+        }
 
         let getter_decls: Vec<_> = self
-            .data
-            .as_ref()
-            .take_struct()
-            .as_ref()
-            .unwrap()
-            .iter()
+            .fields()
             .filter(|f| f.no_getter.is_none())
-            .map(|f| f.declare_getter(&associated_types_idents))
+            .filter(|f| !extracted_fields.contains(&f.index))
+            .map(|f| f.declare_getter(&subst))
             .collect();
 
         let mut_getter_decls: Vec<_> = self
-            .data
-            .as_ref()
-            .take_struct()
-            .as_ref()
-            .unwrap()
-            .iter()
+            .fields()
             .filter(|f| can_be_made_mutable(&f.ty))
             .filter(|f| f.no_getter.is_none())
-            .map(|f| f.declare_mut_getter(&associated_types_idents))
+            .filter(|f| !extracted_fields.contains(&f.index))
+            .map(|f| f.declare_mut_getter(&subst))
             .collect();
 
         let getter_impls: Vec<_> = self
-            .data
-            .as_ref()
-            .take_struct()
-            .as_ref()
-            .unwrap()
-            .iter()
+            .fields()
             .filter(|f| f.no_getter.is_none())
-            .map(|f| f.implement_getter(&associated_types_idents))
+            .filter(|f| !extracted_fields.contains(&f.index))
+            .map(|f| f.implement_getter(&subst))
             .collect();
 
         let mut_getter_impls: Vec<_> = self
-            .data
-            .as_ref()
-            .take_struct()
-            .as_ref()
-            .unwrap()
-            .iter()
+            .fields()
             .filter(|f| can_be_made_mutable(&f.ty))
             .filter(|f| f.no_getter.is_none())
-            .map(|f| f.implement_mut_getter(&associated_types_idents))
+            .filter(|f| !extracted_fields.contains(&f.index))
+            .map(|f| f.implement_mut_getter(&subst))
             .collect();
 
         let haz_impls: Vec<_> = self
-            .data
-            .as_ref()
-            .take_struct()
-            .as_ref()
-            .unwrap()
-            .iter()
+            .fields()
             .filter(|_| self.haz.is_some())
             .filter(|f| f.no_getter.is_none())
             .map(|f| {
                 let field = &f;
-                let field_ident = &field.ident;
+                let field_member = field.member();
                 let field_ty = &field.ty;
 
                 quote! {
                     #[automatically_derived]
                     impl #impl_generics ::haz::Has<#field_ty> for #ident #tys #where_clause {
                         fn access(&self) -> &#field_ty {
-                            &self.#field_ident
+                            &self.#field_member
                         }
                     }
                 }
@@ -170,17 +522,210 @@ impl ToTokens for Degeneric {
             .collect();
 
         if let Some(decl) = self.trait_decl.as_ref() {
+            let vis = &decl.vis;
+            let unsafety = &decl.unsafety;
+            let trait_kw = &decl.trait_kw;
             let trait_name = &decl.ident;
+            let supertraits_with = |subst: &Substitution| -> Punctuated<_, Token![+]> {
+                decl.supertraits
+                    .iter()
+                    .cloned()
+                    .map(|bound| bound_to_associated_ty(bound, subst))
+                    .collect()
+            };
+            let trait_generics_with = |subst: &Substitution| {
+                merge_generics(
+                    &TraitGenerics::from((generics, subst, &associated_consts_idents)).0,
+                    &generics_to_associated_ty(&decl.generics, subst),
+                )
+            };
+            let supertraits: Punctuated<_, Token![+]> = group_supertraits
+                .iter()
+                .chain(&extra_supertraits)
+                .cloned()
+                .chain(supertraits_with(&subst))
+                .collect();
+            let colon = match supertraits.is_empty() {
+                true => None,
+                false => Some(decl.colon_token.unwrap_or_default()),
+            };
+
+            let trait_generics = trait_generics_with(&subst);
+            let (_, trait_ty_generics, trait_where_clause) = trait_generics.split_for_impl();
+
+            let mut trait_impl_generics = merge_generics(&bare_generics, &decl.generics);
+            if !decl.supertraits.is_empty() {
+                let supertraits = &decl.supertraits;
+                trait_impl_generics
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote! { #ident #tys: #supertraits });
+            }
+            for (_, flatten) in &flattened {
+                let trait_path = &flatten.trait_path;
+                trait_impl_generics
+                    .make_where_clause()
+                    .predicates
+                    .push(parse_quote! { #ident #tys: #trait_path });
+            }
+            let (trait_impl_generics, _, trait_impl_where_clause) =
+                trait_impl_generics.split_for_impl();
+
+            let mut trait_items: Vec<TraitItem> = associated_types
+                .iter()
+                .map(|at| parse_quote! { #at })
+                .chain(associated_consts.iter().map(|ac| parse_quote! { #ac }))
+                .chain(getter_decls)
+                .collect();
+
+            let trait_path: Path = parse_quote! { #trait_name #trait_ty_generics };
+            // items which need `&mut self` or `self` end up in the mutable trait in split mode
+            let mut mut_trait_items = mut_getter_decls;
+            let mut mut_trait_impls = mut_getter_impls;
+            let mut trait_impls = getter_impls;
+
+            let getter_fields: Vec<_> = self.fields().filter(|f| f.no_getter.is_none()).collect();
+
+            if let Some(view_ident) = &self.view {
+                let view = PartsStruct::view(
+                    &cfg_attrs,
+                    vis,
+                    view_ident,
+                    &trait_path,
+                    &getter_fields,
+                    &subst,
+                    trait_generics_with,
+                );
+                let view_ty = view.self_ty();
+                let constructor = view.constructor();
+                trait_items.push(parse_quote! {
+                    /// Borrows all fields at once.
+                    fn view(&self) -> #view_ty {
+                        #constructor
+                    }
+                });
+                ts.extend(view.into_token_stream());
+            }
+
+            if let Some(alias_ident) = &self.concrete {
+                let alias = ConcreteAlias {
+                    attrs: &cfg_attrs,
+                    vis,
+                    ident: alias_ident,
+                    target: ident,
+                    target_generics: &bare_generics,
+                    trait_generics: &trait_generics,
+                };
+                let const_params: Vec<_> =
+                    associated_const_params.iter().map(|(cp, _)| *cp).collect();
+                alias.check(&const_params, !associated_type_params.is_empty());
+                ts.extend(alias.emit(&trait_path, &subst));
+                let (decls, impls) = alias.methods();
+                trait_items.extend(decls);
+                trait_impls.extend(impls);
+                let (decls, impls) = alias.mut_methods();
+                mut_trait_items.extend(decls);
+                mut_trait_impls.extend(impls);
+            }
+
+            let type_params: Vec<_> = generics.type_params().map(|tp| &tp.ident).collect();
+            for f in self
+                .fields()
+                .filter(|f| f.no_getter.is_none())
+                .filter(|f| self.setters.is_some() || f.setters.is_some())
+            {
+                let generic = mentioned_ident(&f.ty, &type_params).is_some();
+                let (decls, impls) = f.setters(&subst, generic);
+                mut_trait_items.extend(decls);
+                mut_trait_impls.extend(impls);
+            }
+
+            if self.any_lookup.is_some() {
+                let lookup = AnyLookup::new(generics, self.fields());
+                let (decls, impls) = lookup.methods();
+                trait_items.extend(decls);
+                trait_impls.extend(impls);
+                let (decls, impls) = lookup.mut_methods();
+                mut_trait_items.extend(decls);
+                mut_trait_impls.extend(impls);
+            }
+
+            if self.describe.is_some() {
+                let fields: Vec<_> = self.fields().filter(|f| f.no_getter.is_none()).collect();
+                trait_items.push(describe_dependencies(&associated_idents, &fields, &subst));
+            }
+
+            if self.constructor.is_some() {
+                let fields: Vec<_> = self.fields().collect();
+                let (decls, impls) = constructor(&fields, &associated_consts_idents, &subst);
+                mut_trait_items.extend(decls);
+                mut_trait_impls.extend(impls);
+            }
+
+            if self.into_parts.is_some() {
+                let fields: Vec<_> = self.fields().filter(|f| f.no_getter.is_none()).collect();
+                let (decls, impls) = into_parts(&fields, &subst);
+                mut_trait_items.extend(decls);
+                mut_trait_impls.extend(impls);
+            }
+
+            if let Some(parts_ident) = &self.mut_parts {
+                let parts = PartsStruct::mut_parts(
+                    &cfg_attrs,
+                    vis,
+                    parts_ident,
+                    &trait_path,
+                    &getter_fields,
+                    &subst,
+                    trait_generics_with,
+                );
+                let parts_ty = parts.self_ty();
+                let constructor = parts.constructor();
+                mut_trait_items.push(parse_quote! {
+                    /// Borrows all fields at once, mutably where possible.
+                    fn as_mut_parts(&mut self) -> #parts_ty;
+                });
+                mut_trait_impls.push(parse_quote! {
+                    fn as_mut_parts(&mut self) -> #parts_ty {
+                        #constructor
+                    }
+                });
+                ts.extend(parts.into_token_stream());
+            }
+
+            // e.g. a field named `parts` together with `into_parts`
+            let mut method_names: Vec<&Ident> = vec![];
+            for item in trait_items.iter().chain(&mut_trait_items) {
+                if let TraitItem::Fn(method) = item {
+                    let name = &method.sig.ident;
+                    if method_names.contains(&name) {
+                        abort!(
+                            name,
+                            "degeneric generates the method `{}` twice", name;
+                            help = "rename the field with `#[degeneric(name = \"...\")]` or skip it with `#[degeneric(no_getter)]`"
+                        );
+                    }
+                    method_names.push(name);
+                }
+            }
+
+            let (
+                (mut_getter_decls, mut_getter_impls),
+                (split_mut_getter_decls, split_mut_getter_impls),
+            ) = match &self.split_mut {
+                Some(_) => ((vec![], vec![]), (mut_trait_items, mut_trait_impls)),
+                None => ((mut_trait_items, mut_trait_impls), (vec![], vec![])),
+            };
 
             ts.extend(quote! {
 
                 #(#attrs)*
                 #(#trait_decl_attr)*
                 #dynamize
-                #decl #trait_generics {
-                    #(#associated_types)*
-
-                    #(#getter_decls)*
+                #vis #unsafety #trait_kw #trait_name #trait_generics #colon #supertraits
+                    #trait_where_clause
+                {
+                    #(#trait_items)*
 
                     #(#mut_getter_decls)*
                 }
@@ -188,19 +733,133 @@ impl ToTokens for Degeneric {
                 #(#attrs)*
                 #(#trait_impl_attr)*
                 #[automatically_derived]
-                impl #impl_generics #trait_name #trait_ty_generics for #ident #tys #where_clause {
+                #unsafety impl #trait_impl_generics #trait_name #trait_ty_generics for #ident #tys
+                    #trait_impl_where_clause
+                {
 
                     #(#associated_types_impl)*
 
-                    #(#getter_impls)*
+                    #(#associated_consts_impl)*
+
+                    #(#trait_impls)*
                     #(#mut_getter_impls)*
                 }
             });
+
+            if self.delegate_macro.is_some() {
+                let name = format_ident!("delegate_{}", snake_case(trait_name));
+                let mut_trait_path: Option<Path> = self
+                    .split_mut
+                    .as_ref()
+                    .map(|mut_trait_name| parse_quote! { #mut_trait_name #trait_ty_generics });
+                let mut traits = vec![DelegatedTrait {
+                    path: &trait_path,
+                    items: trait_items
+                        .iter()
+                        .chain(&mut_getter_decls)
+                        .cloned()
+                        .collect(),
+                }];
+                if let Some(path) = &mut_trait_path {
+                    traits.push(DelegatedTrait {
+                        path,
+                        items: split_mut_getter_decls.clone(),
+                    });
+                }
+                ts.extend(emit_delegate_macro(
+                    &cfg_attrs,
+                    &name,
+                    &traits,
+                    &subst,
+                    trait_generics_with,
+                    supertraits_with,
+                ));
+            }
+
+            if self.flattenable.is_some() {
+                let items: Vec<_> = trait_items
+                    .iter()
+                    .chain(&mut_getter_decls)
+                    .cloned()
+                    .collect();
+                ts.extend(emit_flattenable(
+                    &cfg_attrs,
+                    vis,
+                    &trait_path,
+                    &subst,
+                    trait_generics_with,
+                    supertraits_with,
+                    &items,
+                ));
+            }
+
+            if let Some(mut_trait_name) = &self.split_mut {
+                let mut_trait_doc =
+                    format!("Mutable access to the fields behind [`{}`].", trait_name);
+
+                ts.extend(quote! {
+                    #(#cfg_attrs)*
+                    #[doc = #mut_trait_doc]
+                    #(#trait_decl_attr)*
+                    #vis #unsafety #trait_kw #mut_trait_name #trait_generics: #trait_path
+                        #trait_where_clause
+                    {
+                        #(#split_mut_getter_decls)*
+                    }
+
+                    #(#attrs)*
+                    #(#trait_impl_attr)*
+                    #[automatically_derived]
+                    #unsafety impl #trait_impl_generics #mut_trait_name #trait_ty_generics for #ident #tys
+                        #trait_impl_where_clause
+                    {
+                        #(#split_mut_getter_impls)*
+                    }
+                });
+
+                // the read-only trait can be implemented for anything that derefs to an implementor
+                ts.extend(emit_pointer_impls(
+                    trait_impl_attr,
+                    unsafety.as_ref(),
+                    &trait_path,
+                    &trait_items,
+                    &subst,
+                    trait_generics_with,
+                    supertraits_with,
+                ));
+            }
         }
 
         ts.extend(quote! {
             #(#haz_impls)*
         });
+
+        for profile in &self.profile {
+            ts.extend(profile.emit(&cfg_attrs, &self.vis, ident, &bare_generics, &subst));
+        }
+
+        if self.with.is_some() || self.map.is_some() {
+            let methods = TypeChangingMethods {
+                ident,
+                vis: &self.vis,
+                generics: &bare_generics,
+                fields: self.fields().collect(),
+            };
+            let with_methods = associated_type_params
+                .iter()
+                .filter(|_| self.with.is_some())
+                .flat_map(|(tp, _)| methods.with(tp));
+            let map_methods = associated_type_params
+                .iter()
+                .filter(|_| self.map.is_some())
+                .flat_map(|(tp, attrs)| methods.map(tp, attrs.map.as_ref()));
+            ts.extend(quote! {
+                impl #impl_generics #ident #tys #where_clause {
+                    #(#with_methods)*
+                    #(#map_methods)*
+                }
+            });
+        }
     }
 }
 
@@ -1,9 +1,10 @@
 use super::attribute::Attrs;
-use super::type_tools::{make_reference, to_associated_ty};
+use super::flatten::Flatten;
+use super::type_tools::{can_be_made_mutable, make_reference, to_associated_ty, Substitution};
 use darling::FromField;
 use quote::format_ident;
 use syn::spanned::Spanned;
-use syn::{Attribute, Ident, TraitItem, Type};
+use syn::{Attribute, Ident, Index, Member, Path, TraitItem, Type};
 
 #[derive(FromField)]
 #[darling(attributes(degeneric), forward_attrs(allow, cfg, cfg_attr, doc))]
@@ -27,18 +28,84 @@ pub struct FieldDecl {
 
     #[darling(default)]
     pub no_getter: Option<()>,
+
+    #[darling(default)]
+    pub name: Option<Ident>,
+
+    #[darling(default)]
+    pub setters: Option<()>,
+
+    /// Trait groups which expose the field.
+    #[darling(multiple)]
+    pub group: Vec<Ident>,
+
+    /// Existing field trait to implement instead of declaring a new one with `field_traits`.
+    #[darling(default)]
+    pub field_trait: Option<Path>,
+
+    /// Trait of the field type which the struct implements by forwarding to the field.
+    #[darling(default)]
+    pub flatten: Option<Flatten>,
+
+    /// Filled in with `Default::default()` by the generated constructor.
+    #[darling(default)]
+    pub default: Option<()>,
+
+    /// Position of the field within the struct, filled in by [`super::entrypoint`].
+    #[darling(skip)]
+    pub index: usize,
 }
 
 impl FieldDecl {
-    pub fn declare_getter(&self, associated_types_idents: &[&Ident]) -> TraitItem {
-        let name = self.ident.as_ref().unwrap();
+    /// Name used for getters of this field. Unnamed fields are called `_0`, `_1`, ... unless
+    /// they're named by `#[degeneric(name = "...")]`.
+    pub fn getter_name(&self) -> Ident {
+        match (&self.name, &self.ident) {
+            (Some(name), _) | (None, Some(name)) => name.clone(),
+            (None, None) => format_ident!("_{}", self.index, span = self.ty.span()),
+        }
+    }
+
+    /// Name of a method derived from the getter name, e.g. `set_logger`. Unnamed fields are
+    /// already prefixed, so `_0` becomes `set_0`. Named fields keep their leading underscores, so
+    /// `client` and `_client` get `set_client` and `set__client`.
+    pub fn prefixed_name(&self, prefix: &str) -> Ident {
+        let getter = self.getter_name().to_string();
+        let getter = match (&self.name, &self.ident) {
+            (None, None) => getter.trim_start_matches('_'),
+            _ => &getter,
+        };
+        format_ident!("{}_{}", prefix, getter, span = self.ty.span())
+    }
+
+    /// Lint attributes of the methods named by [`Self::prefixed_name`], which aren't snake case
+    /// for fields starting with an underscore.
+    pub fn prefixed_name_attrs(&self) -> Option<Attribute> {
+        match (&self.name, &self.ident) {
+            (Some(name), _) | (None, Some(name)) if name.to_string().starts_with('_') => {
+                Some(syn::parse_quote! { #[allow(non_snake_case)] })
+            }
+            _ => None,
+        }
+    }
+
+    /// Expression used to access the field on `self`.
+    pub fn member(&self) -> Member {
+        match &self.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: self.index as u32,
+                span: self.ty.span(),
+            }),
+        }
+    }
+
+    pub fn declare_getter(&self, subst: &Substitution) -> TraitItem {
+        let name = self.getter_name();
         let docs = &self.attrs;
         let attrs = &self.getter_decl_attr;
         let return_type = pme_unwrap!(
-            make_reference(
-                to_associated_ty(self.ty.clone(), associated_types_idents),
-                None,
-            ),
+            make_reference(to_associated_ty(self.ty.clone(), subst), None,),
             self.ty.span(),
             "unable to turn the type into a reference: {err}"
         );
@@ -50,15 +117,13 @@ impl FieldDecl {
         })
     }
 
-    pub fn implement_getter(&self, associated_types_idents: &[&Ident]) -> TraitItem {
-        let name = self.ident.as_ref().unwrap();
+    pub fn implement_getter(&self, subst: &Substitution) -> TraitItem {
+        let name = self.getter_name();
+        let field = self.member();
         let docs = &self.attrs;
         let attrs = &self.getter_impl_attr;
         let return_type = pme_unwrap!(
-            make_reference(
-                to_associated_ty(self.ty.clone(), associated_types_idents),
-                None,
-            ),
+            make_reference(to_associated_ty(self.ty.clone(), subst), None,),
             self.ty.span(),
             "unable to turn the type into a reference: {err}"
         );
@@ -67,18 +132,18 @@ impl FieldDecl {
             #( #attrs )*
             #( #docs )*
             fn #name (&self) -> #return_type {
-                &self.#name
+                &self.#field
             }
         })
     }
 
-    pub fn declare_mut_getter(&self, associated_types_idents: &[&Ident]) -> TraitItem {
-        let name = format_ident!("{}_mut", self.ident.as_ref().unwrap());
+    pub fn declare_mut_getter(&self, subst: &Substitution) -> TraitItem {
+        let name = format_ident!("{}_mut", self.getter_name());
         let attrs = &self.mut_getter_decl_attr;
         let docs = &self.attrs;
         let return_type = pme_unwrap!(
             make_reference(
-                to_associated_ty(self.ty.clone(), associated_types_idents),
+                to_associated_ty(self.ty.clone(), subst),
                 Some(syn::parse_quote! {mut}),
             ),
             self.ty.span(),
@@ -92,14 +157,14 @@ impl FieldDecl {
         })
     }
 
-    pub fn implement_mut_getter(&self, associated_types_idents: &[&Ident]) -> TraitItem {
-        let field = self.ident.as_ref().unwrap();
-        let name = format_ident!("{}_mut", self.ident.as_ref().unwrap());
+    pub fn implement_mut_getter(&self, subst: &Substitution) -> TraitItem {
+        let field = self.member();
+        let name = format_ident!("{}_mut", self.getter_name());
         let attrs = &self.mut_getter_impl_attr;
         let docs = &self.attrs;
         let return_type = pme_unwrap!(
             make_reference(
-                to_associated_ty(self.ty.clone(), associated_types_idents),
+                to_associated_ty(self.ty.clone(), subst),
                 Some(syn::parse_quote! {mut}),
             ),
             self.ty.span(),
@@ -114,4 +179,59 @@ impl FieldDecl {
             }
         })
     }
+
+    /// Declarations and implementations of `set_x`, `replace_x` and `take_x`. Fields which can't
+    /// be made mutable only get the setter. `take_x` requires `Default`, which can only be put in
+    /// a where clause when the type is `generic`.
+    pub fn setters(&self, subst: &Substitution, generic: bool) -> (Vec<TraitItem>, Vec<TraitItem>) {
+        let field = self.member();
+        let docs = &self.attrs;
+        let ty = to_associated_ty(self.ty.clone(), subst);
+        let set = self.prefixed_name("set");
+        let replace = self.prefixed_name("replace");
+        let take = self.prefixed_name("take");
+        let lints = self.prefixed_name_attrs();
+
+        let mut decls = vec![syn::parse_quote! {
+            #( #docs )*
+            #lints
+            fn #set (&mut self, value: #ty);
+        }];
+        let mut impls = vec![syn::parse_quote! {
+            #( #docs )*
+            fn #set (&mut self, value: #ty) {
+                self.#field = value;
+            }
+        }];
+
+        if can_be_made_mutable(&self.ty) {
+            decls.push(syn::parse_quote! {
+                #( #docs )*
+                #lints
+                fn #replace (&mut self, value: #ty) -> #ty;
+            });
+            impls.push(syn::parse_quote! {
+                #( #docs )*
+                fn #replace (&mut self, value: #ty) -> #ty {
+                    ::core::mem::replace(&mut self.#field, value)
+                }
+            });
+        }
+
+        if can_be_made_mutable(&self.ty) && generic {
+            decls.push(syn::parse_quote! {
+                #( #docs )*
+                #lints
+                fn #take (&mut self) -> #ty where #ty: Default;
+            });
+            impls.push(syn::parse_quote! {
+                #( #docs )*
+                fn #take (&mut self) -> #ty where #ty: Default {
+                    ::core::mem::take(&mut self.#field)
+                }
+            });
+        }
+
+        (decls, impls)
+    }
 }
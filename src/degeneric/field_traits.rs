@@ -0,0 +1,198 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, GenericParam, Generics, Ident, ImplGenerics, Path, Token, Type,
+    TypeGenerics, TypeParamBound, Visibility, WhereClause,
+};
+
+use super::field::FieldDecl;
+use super::generics::where_clause_bounds;
+use super::type_tools::{
+    bound_to_associated_ty, can_be_made_mutable, mentioned_ident, to_associated_ty, Substitution,
+};
+
+/// `http_client` becomes `HttpClient`.
+fn camel_case(ident: &Ident) -> String {
+    ident
+        .to_string()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Trait exposing a single field, e.g. `HasLogger` with the `Logger` associated type.
+pub struct FieldTrait<'d> {
+    pub name: Ident,
+    /// Path of the trait if it's declared by another container, see
+    /// `#[degeneric(field_trait = "...")]`.
+    pub existing: Option<&'d Path>,
+    pub associated: Ident,
+    pub bounds: Punctuated<TypeParamBound, Token![+]>,
+    pub field: &'d FieldDecl,
+}
+
+impl<'d> FieldTrait<'d> {
+    /// When the field is a bare type parameter, its bounds which don't need any other generic
+    /// parameter are kept on the associated type.
+    pub fn new(field: &'d FieldDecl, generics: &Generics, subst: &Substitution) -> Self {
+        let getter = field.getter_name();
+        if field.ident.is_none() && field.name.is_none() {
+            abort!(
+                field.ty,
+                "field traits are named after the fields, but the field `{}` has no name", getter;
+                help = "name it with `#[degeneric(name = \"...\")]`"
+            );
+        }
+        let associated = format_ident!("{}", camel_case(&getter), span = getter.span());
+        let name = format_ident!("Has{}", associated);
+
+        let param = match &field.ty {
+            Type::Path(path) if path.qself.is_none() => path
+                .path
+                .get_ident()
+                .and_then(|ident| generics.type_params().find(|tp| &tp.ident == ident)),
+            _ => None,
+        };
+        let bounds = match param {
+            Some(tp) => {
+                let others: Vec<_> = generics
+                    .params
+                    .iter()
+                    .map(|param| match param {
+                        GenericParam::Lifetime(lt) => &lt.lifetime.ident,
+                        GenericParam::Type(tp) => &tp.ident,
+                        GenericParam::Const(cp) => &cp.ident,
+                    })
+                    .filter(|ident| *ident != &tp.ident)
+                    .collect();
+                let own_subst =
+                    subst.with_replacement(&tp.ident, parse_quote! { Self::#associated });
+                tp.bounds
+                    .iter()
+                    .cloned()
+                    .chain(where_clause_bounds(generics, &tp.ident))
+                    .filter(|bound| mentioned_ident(bound, &others).is_none())
+                    .map(|bound| bound_to_associated_ty(bound, &own_subst))
+                    .collect()
+            }
+            None => Punctuated::new(),
+        };
+
+        Self {
+            name,
+            existing: field.field_trait.as_ref(),
+            associated,
+            bounds,
+            field,
+        }
+    }
+
+    /// Supertrait of the main trait which ties the associated type to the field type, e.g.
+    /// `HasLogger<Logger = Self::Logger>`.
+    pub fn supertrait(&self, subst: &Substitution) -> TypeParamBound {
+        let path = self.path();
+        let associated = &self.associated;
+        let ty = to_associated_ty(self.field.ty.clone(), subst);
+        parse_quote! { #path<#associated = #ty> }
+    }
+
+    fn path(&self) -> Path {
+        match self.existing {
+            Some(path) => path.clone(),
+            None => self.name.clone().into(),
+        }
+    }
+
+    /// Declares the trait unless it already exists and implements it for the struct.
+    pub fn emit(
+        &self,
+        attrs: &[Attribute],
+        vis: &Visibility,
+        ident: &Ident,
+        impl_generics: &ImplGenerics,
+        tys: &TypeGenerics,
+        where_clause: Option<&WhereClause>,
+    ) -> TokenStream {
+        let name = &self.name;
+        let associated = &self.associated;
+        let colon = match self.bounds.is_empty() {
+            true => None,
+            false => Some(<Token![:]>::default()),
+        };
+        let bounds = &self.bounds;
+        let field = self.field;
+        let member = field.member();
+        let ty = &field.ty;
+        let docs = &field.attrs;
+        let getter = field.getter_name();
+        let getter_decl_attrs = &field.getter_decl_attr;
+        let getter_impl_attrs = &field.getter_impl_attr;
+        let (mut_getter_decl, mut_getter_impl) = match can_be_made_mutable(ty) {
+            true => {
+                let mut_getter = format_ident!("{}_mut", getter);
+                let decl_attrs = &field.mut_getter_decl_attr;
+                let impl_attrs = &field.mut_getter_impl_attr;
+                (
+                    quote! {
+                        #(#decl_attrs)*
+                        #(#docs)*
+                        fn #mut_getter(&mut self) -> &mut Self::#associated;
+                    },
+                    quote! {
+                        #(#impl_attrs)*
+                        #(#docs)*
+                        fn #mut_getter(&mut self) -> &mut Self::#associated {
+                            &mut self.#member
+                        }
+                    },
+                )
+            }
+            false => (quote! {}, quote! {}),
+        };
+        let doc = format!("Types which have a `{}`, such as [`{}`].", getter, ident);
+        let decl = match self.existing {
+            Some(_) => quote! {},
+            None => quote! {
+                #(#attrs)*
+                #[doc = #doc]
+                #vis trait #name {
+                    type #associated #colon #bounds;
+
+                    #(#getter_decl_attrs)*
+                    #(#docs)*
+                    fn #getter(&self) -> &Self::#associated;
+
+                    #mut_getter_decl
+                }
+            },
+        };
+        let path = self.path();
+
+        quote! {
+            #decl
+
+            #(#attrs)*
+            #[automatically_derived]
+            impl #impl_generics #path for #ident #tys #where_clause {
+                type #associated = #ty;
+
+                #(#getter_impl_attrs)*
+                #(#docs)*
+                fn #getter(&self) -> &Self::#associated {
+                    &self.#member
+                }
+
+                #mut_getter_impl
+            }
+        }
+    }
+}
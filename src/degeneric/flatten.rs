@@ -0,0 +1,145 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+use syn::punctuated::Punctuated;
+use syn::{
+    parse_quote, Attribute, Generics, Ident, ImplGenerics, Path, PathArguments, Token, TraitItem,
+    Type, TypeGenerics, TypeParamBound, Visibility, WhereClause,
+};
+
+use super::field::FieldDecl;
+use super::forward;
+use super::generics::merge_generics;
+use super::type_tools::{to_associated_ty, Substitution};
+
+/// `#[degeneric(flatten(trait = "DbDepsTrait"))]` on a field whose type implements a trait
+/// generated with `flattenable`.
+#[derive(FromMeta)]
+pub struct Flatten {
+    #[darling(rename = "trait")]
+    pub trait_path: Path,
+}
+
+/// `DbDepsTrait<'a>` becomes `DbDepsTraitFlatten`. The flatten trait has no generics, so the
+/// flattened type outlives every borrow of its container.
+pub fn flatten_trait_path(trait_path: &Path) -> Path {
+    let mut path = trait_path.clone();
+    if let Some(last) = path.segments.last_mut() {
+        last.ident = format_ident!("{}Flatten", last.ident);
+        last.arguments = PathArguments::None;
+    }
+    path
+}
+
+/// Declares the hidden `{Trait}Flatten` trait and implements the `trait_path` trait for all of
+/// its implementors by forwarding `items` to the flattened container.
+pub fn emit_flattenable(
+    attrs: &[Attribute],
+    vis: &Visibility,
+    trait_path: &Path,
+    subst: &Substitution,
+    trait_generics_with: impl Fn(&Substitution) -> Generics,
+    supertraits_with: impl Fn(&Substitution) -> Punctuated<TypeParamBound, Token![+]>,
+    items: &[TraitItem],
+) -> TokenStream {
+    let flatten_path = flatten_trait_path(trait_path);
+    let flatten_name = &flatten_path
+        .segments
+        .last()
+        .expect("the trait has a name")
+        .ident;
+    let trait_name = &trait_path
+        .segments
+        .last()
+        .expect("the trait has a name")
+        .ident;
+    let inner: Type = parse_quote! { <__DegenericT as #flatten_path>::Flattened };
+    let inner_subst = subst.with_owner(inner.clone(), Some(trait_path.clone()));
+    let forwarded = forward::forward_items(items, &inner, trait_path, |rcv| {
+        match (&rcv.reference, &rcv.mutability) {
+            (Some(_), None) => quote! { <__DegenericT as #flatten_path>::flattened(self) },
+            (Some(_), Some(_)) => quote! { <__DegenericT as #flatten_path>::flattened_mut(self) },
+            (None, _) => abort!(rcv, "degeneric can't forward methods taking `self`"),
+        }
+    });
+    let mut impl_generics = merge_generics(
+        &parse_quote! { <__DegenericT: ?Sized + #flatten_path> },
+        &trait_generics_with(&inner_subst),
+    );
+    impl_generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote! { #inner: #trait_path });
+    let supertraits = supertraits_with(&inner_subst);
+    if !supertraits.is_empty() {
+        impl_generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote! { __DegenericT: #supertraits });
+    }
+    let (impl_generics, _, impl_where_clause) = impl_generics.split_for_impl();
+    let doc = format!(
+        "Implements [`{}`] by forwarding to a field, see `#[degeneric(flatten)]`.",
+        trait_name
+    );
+
+    quote! {
+        #(#attrs)*
+        #[doc = #doc]
+        #[doc(hidden)]
+        #vis trait #flatten_name {
+            type Flattened: ?Sized;
+
+            fn flattened(&self) -> &Self::Flattened;
+
+            fn flattened_mut(&mut self) -> &mut Self::Flattened;
+        }
+
+        #(#attrs)*
+        #[automatically_derived]
+        impl #impl_generics #trait_path for __DegenericT #impl_where_clause {
+            #(#forwarded)*
+        }
+    }
+}
+
+impl Flatten {
+    /// Supertrait of the outer trait, e.g. `DbDepsTraitFlatten<Flattened = DbDeps<Self::Conn>>`.
+    /// The inner trait then comes from the blanket impl, which lets the compiler see through its
+    /// associated types, e.g. that `<Self as DbDepsTrait>::Conn` is `Self::Conn`.
+    pub fn supertrait(&self, field: &FieldDecl, subst: &Substitution) -> TypeParamBound {
+        let flatten_path = flatten_trait_path(&self.trait_path);
+        let ty = to_associated_ty(field.ty.clone(), subst);
+        parse_quote! { #flatten_path<Flattened = #ty> }
+    }
+
+    /// Makes the struct flatten `field` into its own trait.
+    pub fn emit(
+        &self,
+        field: &FieldDecl,
+        ident: &Ident,
+        impl_generics: &ImplGenerics,
+        tys: &TypeGenerics,
+        where_clause: Option<&WhereClause>,
+    ) -> TokenStream {
+        let flatten_path = flatten_trait_path(&self.trait_path);
+        let member = field.member();
+        let ty = &field.ty;
+
+        quote! {
+            #[automatically_derived]
+            impl #impl_generics #flatten_path for #ident #tys #where_clause {
+                type Flattened = #ty;
+
+                fn flattened(&self) -> &Self::Flattened {
+                    &self.#member
+                }
+
+                fn flattened_mut(&mut self) -> &mut Self::Flattened {
+                    &mut self.#member
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_quote, FnArg, Pat, Path, Receiver, TraitItem, Type};
+
+/// Implements the `items` of the `trait_path` trait by forwarding each of them to the `inner`
+/// implementor. `receiver` turns the receiver of a method into the expression the call is
+/// forwarded to, e.g. `&self` into `&**self`.
+///
+/// Provided methods are left out, their default implementation is good enough.
+pub fn forward_items(
+    items: &[TraitItem],
+    inner: &Type,
+    trait_path: &Path,
+    receiver: impl Fn(&Receiver) -> TokenStream,
+) -> Vec<TraitItem> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            TraitItem::Type(ty) => {
+                let attrs = &ty.attrs;
+                let ident = &ty.ident;
+                Some(parse_quote! {
+                    #(#attrs)*
+                    type #ident = <#inner as #trait_path>::#ident;
+                })
+            }
+            TraitItem::Const(cnst) => {
+                let attrs = &cnst.attrs;
+                let ident = &cnst.ident;
+                let ty = &cnst.ty;
+                Some(parse_quote! {
+                    #(#attrs)*
+                    const #ident: #ty = <#inner as #trait_path>::#ident;
+                })
+            }
+            TraitItem::Fn(func) if func.default.is_none() => {
+                let attrs = &func.attrs;
+                let sig = &func.sig;
+                let ident = &sig.ident;
+                let args = sig.inputs.iter().map(|input| match input {
+                    FnArg::Receiver(rcv) => receiver(rcv),
+                    FnArg::Typed(pt) => match &*pt.pat {
+                        Pat::Ident(pi) => {
+                            let ident = &pi.ident;
+                            quote! { #ident }
+                        }
+                        pat => abort!(pat, "degeneric can't forward this argument"),
+                    },
+                });
+                if sig.receiver().is_none() {
+                    abort!(
+                        sig.span(),
+                        "degeneric can't forward `{}`, it doesn't take `self`",
+                        ident
+                    );
+                }
+                Some(parse_quote! {
+                    #(#attrs)*
+                    #sig {
+                        <#inner as #trait_path>::#ident(#(#args),*)
+                    }
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -1,47 +1,151 @@
-use super::type_tools::bound_to_associated_ty;
+use super::type_tools::{
+    bound_to_associated_ty, mentioned_idents, predicate_to_associated_ty, Substitution,
+};
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{GenericParam, Generics, Ident, Type, TypeParam, WherePredicate};
+use syn::punctuated::Punctuated;
+use syn::{
+    Attribute, ConstParam, GenericParam, Generics, Ident, Token, Type, TypeParam, TypeParamBound,
+    WhereClause, WherePredicate,
+};
 
-#[derive(Debug)]
-pub struct AssociatedType(pub TypeParam);
+/// Collects bounds placed on the `ident` type parameter in the where clause of `generics`.
+pub fn where_clause_bounds<'g>(
+    generics: &'g Generics,
+    ident: &'g Ident,
+) -> impl Iterator<Item = TypeParamBound> + 'g {
+    generics
+        .where_clause
+        .iter()
+        .flat_map(|wh| &wh.predicates)
+        .flat_map(|pr| {
+            if let WherePredicate::Type(pt) = pr {
+                Some(pt)
+            } else {
+                None
+            }
+        })
+        .flat_map(move |pr| match &pr.bounded_ty {
+            Type::Path(pt) if pt.path.is_ident(ident) => Some(pr.bounds.iter().cloned()),
+            _ => None,
+        })
+        .flatten()
+}
+
+/// Drops `#[degeneric(...)]` attributes, which are only meaningful to the derive itself.
+fn without_degeneric_attrs(attrs: Vec<Attribute>) -> Vec<Attribute> {
+    attrs
+        .into_iter()
+        .filter(|attr| !attr.path().is_ident("degeneric"))
+        .collect()
+}
+
+/// Copy of `generics` which can be emitted outside of the derive input, e.g. on an `impl` block.
+pub fn without_helper_attrs(generics: &Generics) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.params.iter_mut() {
+        match param {
+            GenericParam::Type(tp) => tp.attrs = without_degeneric_attrs(tp.attrs.clone()),
+            GenericParam::Lifetime(lt) => lt.attrs = without_degeneric_attrs(lt.attrs.clone()),
+            GenericParam::Const(cp) => cp.attrs = without_degeneric_attrs(cp.attrs.clone()),
+        }
+    }
+    generics
+}
+
+/// Appends the `extra` generics to `base`, keeping lifetimes in front of the other parameters.
+pub fn merge_generics(base: &Generics, extra: &Generics) -> Generics {
+    let (lifetimes, others): (Vec<_>, Vec<_>) = base
+        .params
+        .iter()
+        .chain(&extra.params)
+        .cloned()
+        .partition(|param| matches!(param, GenericParam::Lifetime(_)));
+    let predicates: Punctuated<_, _> = base
+        .where_clause
+        .iter()
+        .chain(&extra.where_clause)
+        .flat_map(|wh| wh.predicates.iter().cloned())
+        .collect();
+    Generics {
+        lt_token: Some(Default::default()),
+        gt_token: Some(Default::default()),
+        params: lifetimes.into_iter().chain(others).collect(),
+        where_clause: match predicates.len() {
+            0 => None,
+            _ => Some(WhereClause {
+                where_token: Default::default(),
+                predicates,
+            }),
+        },
+    }
+}
 
-impl From<(&TypeParam, &Generics, &Vec<&Ident>)> for AssociatedType {
-    fn from((tp, generics, associated): (&TypeParam, &Generics, &Vec<&Ident>)) -> Self {
-        let mut cl = tp.clone();
-        cl.bounds = cl
+/// Rewrites bounds and where clause of `generics` to refer to associated types.
+pub fn generics_to_associated_ty(generics: &Generics, subst: &Substitution) -> Generics {
+    let mut generics = generics.clone();
+    for param in generics.params.iter_mut() {
+        if let GenericParam::Type(tp) = param {
+            tp.bounds = tp
+                .bounds
+                .iter()
+                .cloned()
+                .map(|bound| bound_to_associated_ty(bound, subst))
+                .collect();
+        }
+    }
+    if let Some(wh) = generics.where_clause.as_mut() {
+        wh.predicates = wh
+            .predicates
+            .iter()
+            .cloned()
+            .map(|pred| predicate_to_associated_ty(pred, subst))
+            .collect();
+    }
+    generics
+}
+
+/// Substitutes the `params` with associated types of the generated trait.
+pub fn associated_substitution<'p>(
+    generics: &Generics,
+    params: impl IntoIterator<Item = &'p TypeParam>,
+) -> Substitution {
+    Substitution::new(params.into_iter().map(|tp| {
+        let bounds = tp
             .bounds
-            .into_iter()
-            .chain(
-                // add bounds on the type from generics where clause
-                generics
-                    .where_clause
-                    .iter()
-                    .flat_map(|wh| &wh.predicates)
-                    .flat_map(|pr| {
-                        if let WherePredicate::Type(pt) = pr {
-                            Some(pt)
-                        } else {
-                            None
-                        }
-                    })
-                    .flat_map(|pr| match &pr.bounded_ty {
-                        Type::Path(pt) if pt.path.is_ident(&tp.ident) => {
-                            Some(pr.bounds.iter().cloned())
-                        }
-                        _ => None,
-                    })
-                    .flatten()
-                    .collect::<Vec<_>>(),
-            )
-            .map(|bound| bound_to_associated_ty(bound, associated))
+            .iter()
+            .cloned()
+            .chain(where_clause_bounds(generics, &tp.ident))
             .collect();
-        // ensure colon exists
-        cl.colon_token = match cl.bounds.len() {
-            0 => None,
-            _ => Some(Default::default()),
-        };
-        Self(cl)
+        (tp.ident.clone(), bounds)
+    }))
+}
+
+/// Type parameter with all of its bounds gathered and rewritten to associated types.
+fn gather_bounds(tp: &TypeParam, generics: &Generics, subst: &Substitution) -> TypeParam {
+    let mut cl = tp.clone();
+    cl.attrs = without_degeneric_attrs(cl.attrs);
+    cl.bounds = cl
+        .bounds
+        .into_iter()
+        // add bounds on the type from generics where clause
+        .chain(where_clause_bounds(generics, &tp.ident).collect::<Vec<_>>())
+        .map(|bound| bound_to_associated_ty(bound, subst))
+        .collect();
+    // ensure colon exists
+    cl.colon_token = match cl.bounds.len() {
+        0 => None,
+        _ => Some(Default::default()),
+    };
+    cl
+}
+
+#[derive(Debug)]
+pub struct AssociatedType(pub TypeParam);
+
+impl From<(&TypeParam, &Generics, &Substitution)> for AssociatedType {
+    fn from((tp, generics, subst): (&TypeParam, &Generics, &Substitution)) -> Self {
+        Self(gather_bounds(tp, generics, subst))
     }
 }
 
@@ -58,41 +162,99 @@ impl ToTokens for AssociatedType {
     }
 }
 
+#[derive(Debug)]
+pub struct AssociatedConst(pub ConstParam);
+
+impl From<&ConstParam> for AssociatedConst {
+    fn from(cp: &ConstParam) -> Self {
+        let mut cl = cp.clone();
+        cl.attrs = without_degeneric_attrs(cl.attrs);
+        cl.eq_token = None;
+        cl.default = None;
+        Self(cl)
+    }
+}
+
+impl ToTokens for AssociatedConst {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attrs = &self.0.attrs;
+        let ident = &self.0.ident;
+        let ty = &self.0.ty;
+        tokens.extend(quote! {
+            #(#attrs)*
+            const #ident: #ty;
+        });
+    }
+}
+
+/// Where clause predicates which can't be expressed as bounds of a single generic parameter,
+/// e.g. `Vec<T>: Debug` or `T::Error: Error`.
+pub fn compound_predicates(g: &Generics) -> impl Iterator<Item = &WherePredicate> {
+    g.where_clause
+        .iter()
+        .flat_map(|wh| &wh.predicates)
+        .filter(move |pred| match pred {
+            WherePredicate::Type(pt) => !g.type_params().any(
+                |tp| matches!(&pt.bounded_ty, Type::Path(path) if path.path.is_ident(&tp.ident)),
+            ),
+            _ => false,
+        })
+}
+
+/// Generics of the generated trait: all lifetimes and the type and const parameters which are
+/// not turned into associated items. The where clause contains the predicates which don't fit
+/// into bounds of associated types.
 pub struct TraitGenerics(pub Generics);
 
-impl From<&Generics> for TraitGenerics {
-    fn from(g: &Generics) -> Self {
+impl From<(&Generics, &Substitution, &Vec<&Ident>)> for TraitGenerics {
+    fn from((g, subst, associated_consts): (&Generics, &Substitution, &Vec<&Ident>)) -> Self {
         let params = g
             .params
             .iter()
             .flat_map(|param| match param {
-                GenericParam::Lifetime(lifetime) => Some(lifetime),
+                GenericParam::Lifetime(lifetime) => {
+                    let mut lifetime = lifetime.clone();
+                    lifetime.bounds.extend(
+                        g.where_clause
+                            .iter()
+                            .flat_map(|wh| &wh.predicates)
+                            .flat_map(|pred| match pred {
+                                WherePredicate::Lifetime(pl) => Some(pl),
+                                _ => None,
+                            })
+                            .filter(|pl| pl.lifetime == lifetime.lifetime)
+                            .flat_map(|pl| pl.bounds.iter())
+                            .cloned()
+                            .collect::<Vec<_>>(),
+                    );
+                    Some(GenericParam::Lifetime(lifetime))
+                }
+                GenericParam::Type(tp) if !subst.contains(&tp.ident) => {
+                    Some(GenericParam::Type(gather_bounds(tp, g, subst)))
+                }
+                GenericParam::Const(cp) if !associated_consts.contains(&&cp.ident) => {
+                    let mut cp = cp.clone();
+                    cp.attrs = without_degeneric_attrs(cp.attrs);
+                    Some(GenericParam::Const(cp))
+                }
                 _ => None,
             })
+            .collect();
+        let predicates: Punctuated<_, _> = compound_predicates(g)
             .cloned()
-            .map(|mut lifetime| {
-                lifetime.bounds.extend(
-                    g.where_clause
-                        .iter()
-                        .flat_map(|wh| &wh.predicates)
-                        .flat_map(|pred| match pred {
-                            WherePredicate::Lifetime(pl) => Some(pl),
-                            _ => None,
-                        })
-                        .filter(|pl| pl.lifetime == lifetime.lifetime)
-                        .flat_map(|pl| pl.bounds.iter())
-                        .cloned()
-                        .collect::<Vec<_>>(),
-                );
-                lifetime
-            })
-            .map(GenericParam::Lifetime)
+            .map(|pred| predicate_to_associated_ty(pred, subst))
             .collect();
         Self(Generics {
             lt_token: g.lt_token,
             gt_token: g.gt_token,
             params,
-            where_clause: None,
+            where_clause: match predicates.len() {
+                0 => None,
+                _ => Some(WhereClause {
+                    where_token: Default::default(),
+                    predicates,
+                }),
+            },
         })
     }
 }
@@ -102,3 +264,67 @@ impl ToTokens for TraitGenerics {
         self.0.to_tokens(tokens)
     }
 }
+
+/// Identifier starting with `base` which doesn't clash with any parameter of `generics`. It's
+/// used for parameters introduced by degeneric, e.g. the container parameter of companion
+/// structs.
+pub fn fresh_ident(generics: &Generics, base: &str) -> Ident {
+    fresh_ident_except(generics, base, &[])
+}
+
+/// Like [`fresh_ident`], but also avoids `others`, e.g. the names of types used next to the new
+/// parameter.
+pub fn fresh_ident_except(generics: &Generics, base: &str, others: &[&Ident]) -> Ident {
+    let taken = |candidate: &str| {
+        others.iter().any(|other| *other == candidate)
+            || generics.params.iter().any(|param| match param {
+                GenericParam::Type(tp) => tp.ident == candidate,
+                GenericParam::Const(cp) => cp.ident == candidate,
+                GenericParam::Lifetime(lt) => lt.lifetime.ident == candidate,
+            })
+    };
+    let candidate = (0..)
+        .map(|n| match n {
+            0 => base.to_string(),
+            n => format!("{}{}", base, n),
+        })
+        .find(|candidate| !taken(candidate))
+        .expect("there's always a free identifier");
+    Ident::new(&candidate, proc_macro2::Span::call_site())
+}
+
+/// Closes `seeds` over the associated type parameters used by the bounds of the parameters in
+/// the set and by the bounds of the parameters which stay generic. A trait exposing `seeds` needs
+/// all of them.
+pub fn required_params<'g>(
+    generics: &'g Generics,
+    associated: &[&'g Ident],
+    seeds: Vec<&'g Ident>,
+) -> Vec<&'g Ident> {
+    let bounds_of = |ident: &Ident| {
+        generics
+            .type_params()
+            .filter(|tp| &tp.ident == ident)
+            .flat_map(|tp| tp.bounds.iter().cloned())
+            .chain(where_clause_bounds(generics, ident))
+            .collect::<Punctuated<_, Token![+]>>()
+    };
+    let mut required = seeds;
+    for tp in generics
+        .type_params()
+        .filter(|tp| !associated.contains(&&tp.ident))
+    {
+        required.extend(mentioned_idents(bounds_of(&tp.ident), associated));
+    }
+    loop {
+        let before = required.len();
+        for ident in required.clone() {
+            required.extend(mentioned_idents(bounds_of(ident), associated));
+        }
+        required.sort_by_key(|ident| associated.iter().position(|a| a == ident));
+        required.dedup();
+        if required.len() == before {
+            return required;
+        }
+    }
+}
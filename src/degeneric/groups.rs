@@ -0,0 +1,140 @@
+use darling::FromMeta;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse_quote, Attribute, GenericParam, Generics, Ident, ImplGenerics, Path, Type, TypeGenerics,
+    TypeParamBound, Visibility, WhereClause,
+};
+
+use super::field::FieldDecl;
+use super::generics::AssociatedType;
+use super::type_tools::{can_be_made_mutable, Substitution};
+
+/// Trait with only a part of the fields, declared by
+/// `#[degeneric(trait_group(name = "LoggingDeps", vis = "pub"))]`.
+#[derive(FromMeta)]
+pub struct TraitGroup {
+    pub name: Ident,
+
+    #[darling(default)]
+    pub vis: Option<Visibility>,
+}
+
+/// The trait generated for a [`TraitGroup`] with the fields which belong to it and the
+/// associated types these fields need.
+pub struct GroupTrait<'d> {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub name: &'d Ident,
+    pub generics: Generics,
+    pub params: Vec<&'d Ident>,
+    pub associated_types: Vec<AssociatedType>,
+    pub fields: Vec<&'d FieldDecl>,
+}
+
+impl GroupTrait<'_> {
+    fn args(&self) -> Vec<TokenStream> {
+        self.generics
+            .params
+            .iter()
+            .map(|param| match param {
+                GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+                GenericParam::Type(tp) => tp.ident.to_token_stream(),
+                GenericParam::Const(cp) => cp.ident.to_token_stream(),
+            })
+            .collect()
+    }
+
+    /// Path of the trait, e.g. `LoggingDeps<'a>`.
+    pub fn path(&self) -> Path {
+        self.bound(&[])
+    }
+
+    /// Path of the trait with the given associated types bound, e.g.
+    /// `LoggingDeps<'a, Logger = <Self as OtherDeps<'a>>::Logger>`.
+    pub fn bound(&self, bindings: &[(&Ident, Type)]) -> Path {
+        let name = self.name;
+        let args = self
+            .args()
+            .into_iter()
+            .chain(bindings.iter().map(|(ident, ty)| quote! { #ident = #ty }))
+            .collect::<Vec<_>>();
+        if args.is_empty() {
+            parse_quote! { #name }
+        } else {
+            parse_quote! { #name<#(#args),*> }
+        }
+    }
+
+    /// Declares the trait and implements it for the struct.
+    pub fn emit(
+        &self,
+        subst: &Substitution,
+        ident: &Ident,
+        impl_generics: &ImplGenerics,
+        tys: &TypeGenerics,
+        where_clause: Option<&WhereClause>,
+    ) -> TokenStream {
+        let attrs = &self.attrs;
+        let vis = &self.vis;
+        let name = self.name;
+        let path = self.path();
+        let generics = &self.generics;
+        let trait_where_clause = &self.generics.where_clause;
+        let associated_types = &self.associated_types;
+        let params = &self.params;
+        let getter_decls = self.fields.iter().map(|f| f.declare_getter(subst));
+        let getter_impls = self.fields.iter().map(|f| f.implement_getter(subst));
+        let mutable_fields = || self.fields.iter().filter(|f| can_be_made_mutable(&f.ty));
+        let mut_getter_decls = mutable_fields().map(|f| f.declare_mut_getter(subst));
+        let mut_getter_impls = mutable_fields().map(|f| f.implement_mut_getter(subst));
+        let doc = format!("Part of the fields of [`{}`].", ident);
+
+        quote! {
+            #(#attrs)*
+            #[doc = #doc]
+            #vis trait #name #generics #trait_where_clause {
+                #(#associated_types)*
+
+                #(#getter_decls)*
+
+                #(#mut_getter_decls)*
+            }
+
+            #(#attrs)*
+            #[automatically_derived]
+            impl #impl_generics #path for #ident #tys #where_clause {
+                #(type #params = #params;)*
+
+                #(#getter_impls)*
+                #(#mut_getter_impls)*
+            }
+        }
+    }
+}
+
+/// Bounds which make the main trait extend all the groups. Every associated type is owned by the
+/// first group which declares it, the other groups are bound to use the same type.
+pub fn group_supertraits(groups: &[GroupTrait]) -> Vec<TypeParamBound> {
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| {
+            let bindings: Vec<_> = group
+                .params
+                .iter()
+                .flat_map(|param| {
+                    groups[..i]
+                        .iter()
+                        .find(|owner| owner.params.contains(param))
+                        .map(|owner| {
+                            let owner = owner.path();
+                            (*param, parse_quote! { <Self as #owner>::#param })
+                        })
+                })
+                .collect();
+            let bound = group.bound(&bindings);
+            parse_quote! { #bound }
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use syn::{parse_quote, TraitItem};
+
+use super::field::FieldDecl;
+use super::type_tools::{to_associated_ty, Substitution};
+
+/// Declarations and implementations of `into_parts`, which moves all `fields` out of the
+/// container, and `into_<field>` for each of them.
+pub fn into_parts(fields: &[&FieldDecl], subst: &Substitution) -> (Vec<TraitItem>, Vec<TraitItem>) {
+    let tys: Vec<_> = fields
+        .iter()
+        .map(|f| to_associated_ty(f.ty.clone(), subst))
+        .collect();
+    let members: Vec<_> = fields.iter().map(|f| f.member()).collect();
+
+    let mut decls = vec![parse_quote! {
+        /// Moves all fields out of the container.
+        fn into_parts(self) -> (#(#tys,)*) where Self: Sized;
+    }];
+    let mut impls = vec![parse_quote! {
+        fn into_parts(self) -> (#(#tys,)*) where Self: Sized {
+            (#(self.#members,)*)
+        }
+    }];
+
+    for ((f, ty), member) in fields.iter().zip(&tys).zip(&members) {
+        let name = f.prefixed_name("into");
+        let lints = f.prefixed_name_attrs();
+        let docs = &f.attrs;
+        decls.push(parse_quote! {
+            #(#docs)*
+            #lints
+            fn #name(self) -> #ty where Self: Sized;
+        });
+        impls.push(parse_quote! {
+            #(#docs)*
+            fn #name(self) -> #ty where Self: Sized {
+                self.#member
+            }
+        });
+    }
+
+    (decls, impls)
+}
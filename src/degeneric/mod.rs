@@ -1,8 +1,24 @@
+mod any_lookup;
 mod attribute;
+mod builders;
+mod bundle;
+mod concrete;
+mod constructor;
+mod delegate;
+mod describe;
 mod dynamize;
 mod entrypoint;
 mod field;
+mod field_traits;
+mod flatten;
+mod forward;
 mod generics;
+mod groups;
+mod into_parts;
+mod parts;
+mod profile;
+mod split_mut;
 mod type_tools;
 
+pub use self::bundle::process_bundle;
 pub use self::entrypoint::process_struct;
@@ -0,0 +1,272 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    parse_quote, Attribute, Expr, GenericArgument, GenericParam, Generics, Ident, Lifetime, Path,
+    Type, Visibility,
+};
+
+use super::field::FieldDecl;
+use super::generics::{fresh_ident, merge_generics};
+use super::type_tools::{can_be_made_mutable, make_reference, to_associated_ty, Substitution};
+
+fn trait_name(trait_path: &Path) -> &Ident {
+    &trait_path
+        .segments
+        .last()
+        .expect("the trait has a name")
+        .ident
+}
+
+/// Field of a [`PartsStruct`] together with the expression which fills it in from `self`.
+pub struct PartsField {
+    pub attrs: Vec<Attribute>,
+    pub ident: Ident,
+    pub ty: Type,
+    pub init: Expr,
+}
+
+/// Struct generated next to the trait which borrows all fields of an implementor at once, e.g.
+/// `ContainerMutParts<'p, C: ContainerTrait>`.
+pub struct PartsStruct {
+    pub attrs: Vec<Attribute>,
+    pub vis: Visibility,
+    pub ident: Ident,
+    pub lifetime: Lifetime,
+    pub container: Ident,
+    pub generics: Generics,
+    pub fields: Vec<PartsField>,
+}
+
+impl PartsStruct {
+    /// Struct returned by `view`, which borrows every field through its getter.
+    pub fn view(
+        attrs: &[Attribute],
+        vis: &Visibility,
+        ident: &Ident,
+        trait_path: &Path,
+        fields: &[&FieldDecl],
+        subst: &Substitution,
+        trait_generics_with: impl Fn(&Substitution) -> Generics,
+    ) -> Self {
+        let doc = format!(
+            "Shared borrows of all fields of a [`{}`] implementor.",
+            trait_name(trait_path)
+        );
+        let (mut view, view_subst) = Self::new(
+            parse_quote! { #(#attrs)* #[doc = #doc] },
+            vis.clone(),
+            ident.clone(),
+            "v",
+            trait_path,
+            subst,
+            trait_generics_with,
+        );
+        view.fields = fields
+            .iter()
+            .map(|f| {
+                let getter = f.getter_name();
+                let ty = pme_unwrap!(
+                    make_reference(to_associated_ty(f.ty.clone(), &view_subst), None),
+                    f.ty.span(),
+                    "unable to turn the type into a reference: {err}"
+                );
+                PartsField {
+                    attrs: f.attrs.clone(),
+                    ident: getter.clone(),
+                    ty: view.borrowed(ty),
+                    init: parse_quote! { self.#getter() },
+                }
+            })
+            .collect();
+        view
+    }
+
+    /// Struct returned by `as_mut_parts`, which borrows every field mutably where possible.
+    pub fn mut_parts(
+        attrs: &[Attribute],
+        vis: &Visibility,
+        ident: &Ident,
+        trait_path: &Path,
+        fields: &[&FieldDecl],
+        subst: &Substitution,
+        trait_generics_with: impl Fn(&Substitution) -> Generics,
+    ) -> Self {
+        let doc = format!(
+            "Mutable borrows of all fields of a [`{}`] implementor.",
+            trait_name(trait_path)
+        );
+        let (mut parts, parts_subst) = Self::new(
+            parse_quote! { #(#attrs)* #[doc = #doc] },
+            vis.clone(),
+            ident.clone(),
+            "p",
+            trait_path,
+            subst,
+            trait_generics_with,
+        );
+        parts.fields = fields
+            .iter()
+            .map(|f| {
+                let member = f.member();
+                let mutable = can_be_made_mutable(&f.ty);
+                let ty = pme_unwrap!(
+                    make_reference(
+                        to_associated_ty(f.ty.clone(), &parts_subst),
+                        if mutable {
+                            Some(parse_quote! { mut })
+                        } else {
+                            None
+                        },
+                    ),
+                    f.ty.span(),
+                    "unable to turn the type into a reference: {err}"
+                );
+                PartsField {
+                    attrs: f.attrs.clone(),
+                    ident: f.getter_name(),
+                    ty: parts.borrowed(ty),
+                    init: if mutable {
+                        parse_quote! { &mut self.#member }
+                    } else {
+                        parse_quote! { self.#member }
+                    },
+                }
+            })
+            .collect();
+        parts
+    }
+
+    /// Prepares the generics of the struct. The fields should then be rewritten with the returned
+    /// substitution, which takes the associated types from the container parameter.
+    fn new(
+        attrs: Vec<Attribute>,
+        vis: Visibility,
+        ident: Ident,
+        lifetime: &str,
+        trait_path: &Path,
+        subst: &Substitution,
+        trait_generics_with: impl Fn(&Substitution) -> Generics,
+    ) -> (Self, Substitution) {
+        let trait_generics = trait_generics_with(subst);
+        let lifetime = Lifetime {
+            apostrophe: proc_macro2::Span::call_site(),
+            ident: fresh_ident(&trait_generics, lifetime),
+        };
+        let container = fresh_ident(&trait_generics, "C");
+        let container_ty: Type = parse_quote! { #container };
+        let subst = subst.with_owner(container_ty, Some(trait_path.clone()));
+
+        let mut generics = merge_generics(
+            &parse_quote! { <#lifetime, #container: ?Sized> },
+            &trait_generics_with(&subst),
+        );
+        generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote! { #container: #trait_path });
+
+        let parts = Self {
+            attrs,
+            vis,
+            ident,
+            lifetime,
+            container,
+            generics,
+            fields: vec![],
+        };
+        (parts, subst)
+    }
+
+    /// The struct borrowed from `Self` for the lifetime of the method receiver.
+    pub fn self_ty(&self) -> Type {
+        let ident = &self.ident;
+        let args = self.generics.params.iter().map(|param| -> GenericArgument {
+            match param {
+                GenericParam::Lifetime(lt) if lt.lifetime == self.lifetime => parse_quote! { '_ },
+                GenericParam::Lifetime(lt) => {
+                    let lifetime = &lt.lifetime;
+                    parse_quote! { #lifetime }
+                }
+                GenericParam::Type(tp) if tp.ident == self.container => parse_quote! { Self },
+                GenericParam::Type(tp) => {
+                    let ident = &tp.ident;
+                    parse_quote! { #ident }
+                }
+                GenericParam::Const(cp) => {
+                    let ident = &cp.ident;
+                    parse_quote! { #ident }
+                }
+            }
+        });
+        parse_quote! { #ident<#(#args),*> }
+    }
+
+    /// Makes a reference type returned by a getter borrow for the lifetime of the struct.
+    pub fn borrowed(&self, ty: Type) -> Type {
+        match ty {
+            Type::Reference(mut rf) if rf.lifetime.is_none() => {
+                rf.lifetime = Some(self.lifetime.clone());
+                Type::Reference(rf)
+            }
+            ty => ty,
+        }
+    }
+
+    /// Expression building the struct from the fields of `self`.
+    pub fn constructor(&self) -> Expr {
+        let ident = &self.ident;
+        let inits = self.fields.iter().map(|field| {
+            let cfg_attrs = field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("cfg_attr"));
+            let ident = &field.ident;
+            let init = &field.init;
+            quote! { #(#cfg_attrs)* #ident: #init }
+        });
+        parse_quote! {
+            #ident {
+                #(#inits,)*
+                __degeneric_marker: ::core::marker::PhantomData,
+            }
+        }
+    }
+}
+
+impl ToTokens for PartsStruct {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let attrs = &self.attrs;
+        let vis = &self.vis;
+        let ident = &self.ident;
+        let (_, _, where_clause) = self.generics.split_for_impl();
+        let generics = &self.generics;
+        let lifetime = &self.lifetime;
+        let field_attrs = self.fields.iter().map(|field| &field.attrs);
+        let field_idents = self.fields.iter().map(|field| &field.ident);
+        let field_tys = self.fields.iter().map(|field| &field.ty);
+        // every parameter has to be used by the struct
+        let markers = self.generics.params.iter().map(|param| match param {
+            GenericParam::Lifetime(lt) => {
+                let lt = &lt.lifetime;
+                quote! { &#lt (), }
+            }
+            GenericParam::Type(tp) => {
+                let ident = &tp.ident;
+                quote! { &#lifetime #ident, }
+            }
+            GenericParam::Const(_) => quote! {},
+        });
+        tokens.extend(quote! {
+            #(#attrs)*
+            #vis struct #ident #generics #where_clause {
+                #(
+                    #(#field_attrs)*
+                    pub #field_idents: #field_tys,
+                )*
+                #[doc(hidden)]
+                pub __degeneric_marker: ::core::marker::PhantomData<(#(#markers)*)>,
+            }
+        });
+    }
+}
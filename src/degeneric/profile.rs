@@ -0,0 +1,152 @@
+use darling::{Error, FromMeta, Result};
+use proc_macro2::{Span, TokenStream};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
+use syn::{
+    Attribute, Expr, ExprLit, GenericParam, Generics, Ident, Lit, LitStr, Meta, Type, Visibility,
+};
+
+use super::generics::generics_to_associated_ty;
+use super::type_tools::Substitution;
+
+/// Concrete types for the type parameters of the struct, e.g.
+/// `profile(name = "Prod", Logger = "StdoutLogger")`.
+pub struct Profile {
+    name: Ident,
+    assignments: Vec<(Ident, Type)>,
+    span: Span,
+}
+
+fn lit_str(expr: &Expr) -> Result<&LitStr> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(lit), ..
+        }) => Ok(lit),
+        expr => Err(Error::unexpected_expr_type(expr).with_span(expr)),
+    }
+}
+
+impl FromMeta for Profile {
+    fn from_meta(item: &Meta) -> Result<Self> {
+        let list = item.require_list()?;
+        let items = list.parse_args_with(
+            syn::punctuated::Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated,
+        )?;
+        let mut name = None;
+        let mut assignments = vec![];
+        for nv in items {
+            let key = nv
+                .path
+                .get_ident()
+                .ok_or_else(|| Error::unsupported_format("path").with_span(&nv.path))?;
+            let value = lit_str(&nv.value)?;
+            if key == "name" {
+                name = Some(value.parse()?);
+            } else {
+                assignments.push((key.clone(), value.parse()?));
+            }
+        }
+        Ok(Self {
+            name: name.ok_or_else(|| Error::missing_field("name").with_span(item))?,
+            assignments,
+            span: item.span(),
+        })
+    }
+}
+
+impl Profile {
+    /// Type alias named after the profile, e.g. `ProdContainer`, together with a check that the
+    /// assigned types satisfy the bounds of the parameters.
+    pub fn emit(
+        &self,
+        attrs: &[Attribute],
+        vis: &Visibility,
+        ident: &Ident,
+        generics: &Generics,
+        subst: &Substitution,
+    ) -> TokenStream {
+        for (key, _) in &self.assignments {
+            if !subst.contains(key) {
+                abort!(
+                    key,
+                    "`{}` isn't a type parameter which the profile can assign", key;
+                    help = "preserved parameters stay generic parameters of the type alias"
+                );
+            }
+        }
+        let mut assigned = subst.clone();
+        for tp in generics
+            .type_params()
+            .filter(|tp| subst.contains(&tp.ident))
+        {
+            match self.assignments.iter().find(|(key, _)| key == &tp.ident) {
+                Some((_, ty)) => assigned = assigned.with_replacement(&tp.ident, ty.clone()),
+                None => abort!(
+                    self.span,
+                    "profile `{}` doesn't assign the type parameter `{}`",
+                    self.name,
+                    tp.ident
+                ),
+            }
+        }
+        let subst = assigned;
+
+        let alias = format_ident!("{}{}", self.name, ident);
+        let params: Vec<_> = generics
+            .params
+            .iter()
+            .filter(|param| !matches!(param, GenericParam::Type(tp) if subst.contains(&tp.ident)))
+            .collect();
+        let alias_params = params.iter().map(|param| match param {
+            GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+            GenericParam::Type(tp) => tp.ident.to_token_stream(),
+            GenericParam::Const(cp) => {
+                let ident = &cp.ident;
+                let ty = &cp.ty;
+                quote! { const #ident: #ty }
+            }
+        });
+        let alias_args: Vec<_> = params
+            .iter()
+            .map(|param| match param {
+                GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+                GenericParam::Type(tp) => tp.ident.to_token_stream(),
+                GenericParam::Const(cp) => cp.ident.to_token_stream(),
+            })
+            .collect();
+        let struct_args = generics.params.iter().map(|param| match param {
+            GenericParam::Lifetime(lt) => lt.lifetime.to_token_stream(),
+            GenericParam::Type(tp) => match subst.replacement(&tp.ident) {
+                Some(ty) => ty.to_token_stream(),
+                None => tp.ident.to_token_stream(),
+            },
+            GenericParam::Const(cp) => cp.ident.to_token_stream(),
+        });
+        let doc = format!("[`{}`] with the `{}` profile.", ident, self.name);
+
+        // the generics of the check are the parameters left in the alias with the assigned types
+        // substituted into their bounds
+        let mut check_generics = generics_to_associated_ty(generics, &subst);
+        check_generics.params = check_generics
+            .params
+            .into_iter()
+            .filter(|param| !matches!(param, GenericParam::Type(tp) if subst.contains(&tp.ident)))
+            .collect();
+        let (check_params, _, check_where_clause) = check_generics.split_for_impl();
+        let check = quote_spanned! {self.span=>
+            const _: () = {
+                #[allow(dead_code)]
+                fn check #check_params (_: #alias<#(#alias_args),*>) #check_where_clause {}
+            };
+        };
+
+        quote! {
+            #(#attrs)*
+            #[doc = #doc]
+            #vis type #alias<#(#alias_params),*> = #ident<#(#struct_args),*>;
+
+            #check
+        }
+    }
+}
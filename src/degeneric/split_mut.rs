@@ -0,0 +1,78 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_quote, Generics, Path, Token, TraitItem, Type, TypeParamBound};
+
+use super::attribute::Attrs;
+use super::forward;
+use super::generics::merge_generics;
+use super::type_tools::Substitution;
+
+/// Implements the read-only `trait_path` trait for `&T`, `&mut T`, `Box<T>`, `Rc<T>` and `Arc<T>`
+/// by forwarding `items` to `T`.
+pub fn emit_pointer_impls(
+    attrs: &[Attrs],
+    unsafety: Option<&Token![unsafe]>,
+    trait_path: &Path,
+    items: &[TraitItem],
+    subst: &Substitution,
+    trait_generics_with: impl Fn(&Substitution) -> Generics,
+    supertraits_with: impl Fn(&Substitution) -> Punctuated<TypeParamBound, Token![+]>,
+) -> TokenStream {
+    let inner: Type = parse_quote! { __DegenericT };
+    let forward_subst = subst.with_owner(inner.clone(), Some(trait_path.clone()));
+    let forwarded = forward::forward_items(items, &inner, trait_path, |rcv| {
+        match (&rcv.reference, &rcv.mutability) {
+            (Some(_), None) => quote! { &**self },
+            (Some(_), Some(_)) => quote! { &mut **self },
+            (None, _) => abort!(rcv, "degeneric can't forward methods taking `self`"),
+        }
+    });
+    let forward_supertraits = supertraits_with(&forward_subst);
+    let wrappers: [(Generics, Type); 5] = [
+        (
+            parse_quote! { <'__degeneric, __DegenericT: ?Sized> },
+            parse_quote! { &'__degeneric __DegenericT },
+        ),
+        (
+            parse_quote! { <'__degeneric, __DegenericT: ?Sized> },
+            parse_quote! { &'__degeneric mut __DegenericT },
+        ),
+        (
+            parse_quote! { <__DegenericT: ?Sized> },
+            parse_quote! { ::std::boxed::Box<__DegenericT> },
+        ),
+        (
+            parse_quote! { <__DegenericT: ?Sized> },
+            parse_quote! { ::std::rc::Rc<__DegenericT> },
+        ),
+        (
+            parse_quote! { <__DegenericT: ?Sized> },
+            parse_quote! { ::std::sync::Arc<__DegenericT> },
+        ),
+    ];
+
+    let mut ts = TokenStream::new();
+    for (wrapper_generics, wrapper) in wrappers {
+        let mut forward_generics =
+            merge_generics(&wrapper_generics, &trait_generics_with(&forward_subst));
+        let predicates = &mut forward_generics.make_where_clause().predicates;
+        predicates.push(parse_quote! { #inner: #trait_path });
+        if !forward_supertraits.is_empty() {
+            predicates.push(parse_quote! { #wrapper: #forward_supertraits });
+        }
+        let (forward_impl_generics, _, forward_where_clause) = forward_generics.split_for_impl();
+
+        ts.extend(quote! {
+            #(#attrs)*
+            #[automatically_derived]
+            #unsafety impl #forward_impl_generics #trait_path for #wrapper
+                #forward_where_clause
+            {
+                #(#forwarded)*
+            }
+        });
+    }
+    ts
+}
@@ -1,59 +1,71 @@
-use quote::format_ident;
+use super::Substitution;
+use proc_macro_error::abort;
+use syn::parse_quote;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::AngleBracketedGenericArguments;
+use syn::AssocConst;
+use syn::AssocType;
 use syn::BareFnArg;
+use syn::Constraint;
 use syn::GenericArgument;
 use syn::Ident;
 use syn::ParenthesizedGenericArguments;
 use syn::Path;
 use syn::PathArguments;
 use syn::PathSegment;
+use syn::PredicateType;
+use syn::QSelf;
 use syn::ReturnType;
+use syn::TraitBoundModifier;
 use syn::Type;
 use syn::TypeArray;
 use syn::TypeBareFn;
 use syn::TypeGroup;
+use syn::TypeImplTrait;
 use syn::TypeParamBound;
 use syn::TypeParen;
 use syn::TypePath;
 use syn::TypePtr;
 use syn::TypeReference;
 use syn::TypeSlice;
+use syn::TypeTraitObject;
 use syn::TypeTuple;
+use syn::WherePredicate;
 
-fn array_to_associated_ty(ta: TypeArray, generic_idents: &[&Ident]) -> TypeArray {
+fn array_to_associated_ty(ta: TypeArray, subst: &Substitution) -> TypeArray {
     TypeArray {
         bracket_token: ta.bracket_token,
         semi_token: ta.semi_token,
         len: ta.len,
-        elem: Box::new(to_associated_ty(*ta.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*ta.elem, subst)),
     }
 }
 
-fn bare_fn_arg_to_associated_ty(bfa: BareFnArg, generic_idents: &[&Ident]) -> BareFnArg {
+fn bare_fn_arg_to_associated_ty(bfa: BareFnArg, subst: &Substitution) -> BareFnArg {
     BareFnArg {
         attrs: bfa.attrs,
         name: bfa.name,
-        ty: to_associated_ty(bfa.ty, generic_idents),
+        ty: to_associated_ty(bfa.ty, subst),
     }
 }
 
-fn return_type_to_associated_ty(rt: ReturnType, generic_idents: &[&Ident]) -> ReturnType {
+fn return_type_to_associated_ty(rt: ReturnType, subst: &Substitution) -> ReturnType {
     match rt {
         ReturnType::Type(rarr, ty) => {
-            ReturnType::Type(rarr, Box::new(to_associated_ty(*ty, generic_idents)))
+            ReturnType::Type(rarr, Box::new(to_associated_ty(*ty, subst)))
         }
         x => x,
     }
 }
 
-fn bare_fn_to_associated_ty(bft: TypeBareFn, generic_idents: &[&Ident]) -> TypeBareFn {
+fn bare_fn_to_associated_ty(bft: TypeBareFn, subst: &Substitution) -> TypeBareFn {
     let inputs = bft
         .inputs
         .into_iter()
-        .map(|inp| bare_fn_arg_to_associated_ty(inp, generic_idents))
+        .map(|inp| bare_fn_arg_to_associated_ty(inp, subst))
         .collect();
-    let output = return_type_to_associated_ty(bft.output, generic_idents);
+    let output = return_type_to_associated_ty(bft.output, subst);
 
     TypeBareFn {
         lifetimes: bft.lifetimes,
@@ -67,49 +79,73 @@ fn bare_fn_to_associated_ty(bft: TypeBareFn, generic_idents: &[&Ident]) -> TypeB
     }
 }
 
-fn group_to_associated_ty(gr: TypeGroup, generic_idents: &[&Ident]) -> TypeGroup {
+fn group_to_associated_ty(gr: TypeGroup, subst: &Substitution) -> TypeGroup {
     TypeGroup {
         group_token: gr.group_token,
-        elem: Box::new(to_associated_ty(*gr.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*gr.elem, subst)),
     }
 }
 
-fn paren_to_associated_ty(pa: TypeParen, generic_idents: &[&Ident]) -> TypeParen {
+fn paren_to_associated_ty(pa: TypeParen, subst: &Substitution) -> TypeParen {
     TypeParen {
         paren_token: pa.paren_token,
-        elem: Box::new(to_associated_ty(*pa.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*pa.elem, subst)),
     }
 }
 
-fn ptr_to_associated_ty(ptr: TypePtr, generic_idents: &[&Ident]) -> TypePtr {
+fn ptr_to_associated_ty(ptr: TypePtr, subst: &Substitution) -> TypePtr {
     TypePtr {
         const_token: ptr.const_token,
-        elem: Box::new(to_associated_ty(*ptr.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*ptr.elem, subst)),
         mutability: ptr.mutability,
         star_token: ptr.star_token,
     }
 }
 
-fn reference_to_associated_ty(rf: TypeReference, generic_idents: &[&Ident]) -> TypeReference {
+fn reference_to_associated_ty(rf: TypeReference, subst: &Substitution) -> TypeReference {
     TypeReference {
         and_token: rf.and_token,
         lifetime: rf.lifetime,
         mutability: rf.mutability,
-        elem: Box::new(to_associated_ty(*rf.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*rf.elem, subst)),
     }
 }
-fn slice_to_associated_ty(slice: TypeSlice, generic_idents: &[&Ident]) -> TypeSlice {
+fn slice_to_associated_ty(slice: TypeSlice, subst: &Substitution) -> TypeSlice {
     TypeSlice {
         bracket_token: slice.bracket_token,
-        elem: Box::new(to_associated_ty(*slice.elem, generic_idents)),
+        elem: Box::new(to_associated_ty(*slice.elem, subst)),
     }
 }
 
-fn tuple_to_associated_ty(tup: TypeTuple, generic_idents: &[&Ident]) -> TypeTuple {
+fn bounds_to_associated_ty(
+    bounds: Punctuated<TypeParamBound, syn::Token![+]>,
+    subst: &Substitution,
+) -> Punctuated<TypeParamBound, syn::Token![+]> {
+    bounds
+        .into_iter()
+        .map(|bound| bound_to_associated_ty(bound, subst))
+        .collect()
+}
+
+fn trait_object_to_associated_ty(to: TypeTraitObject, subst: &Substitution) -> TypeTraitObject {
+    TypeTraitObject {
+        dyn_token: to.dyn_token,
+        bounds: bounds_to_associated_ty(to.bounds, subst),
+    }
+}
+
+fn impl_trait_to_associated_ty(it: TypeImplTrait, subst: &Substitution) -> TypeImplTrait {
+    TypeImplTrait {
+        impl_token: it.impl_token,
+        bounds: bounds_to_associated_ty(it.bounds, subst),
+    }
+}
+
+fn tuple_to_associated_ty(tup: TypeTuple, subst: &Substitution) -> TypeTuple {
     let elems = tup
         .elems
         .into_iter()
-        .map(|ty| to_associated_ty(ty, generic_idents))
+        .map(|ty| to_associated_ty(ty, subst))
         .collect();
     TypeTuple {
         paren_token: tup.paren_token,
@@ -117,22 +153,44 @@ fn tuple_to_associated_ty(tup: TypeTuple, generic_idents: &[&Ident]) -> TypeTupl
     }
 }
 
-fn arg_to_associated_ty(arg: GenericArgument, generic_idents: &[&Ident]) -> GenericArgument {
+fn arg_to_associated_ty(arg: GenericArgument, subst: &Substitution) -> GenericArgument {
     match arg {
-        GenericArgument::Type(ty) => GenericArgument::Type(to_associated_ty(ty, generic_idents)),
+        GenericArgument::Type(ty) => GenericArgument::Type(to_associated_ty(ty, subst)),
         GenericArgument::Lifetime(lt) => GenericArgument::Lifetime(lt),
-        _ => panic!("unexpected item found in angular bracketed generic arguments"),
+        GenericArgument::AssocType(at) => GenericArgument::AssocType(AssocType {
+            ident: at.ident,
+            generics: at.generics.map(|ab| ab_to_associated_ty(ab, subst)),
+            eq_token: at.eq_token,
+            ty: to_associated_ty(at.ty, subst),
+        }),
+        GenericArgument::AssocConst(ac) => GenericArgument::AssocConst(AssocConst {
+            ident: ac.ident,
+            generics: ac.generics.map(|ab| ab_to_associated_ty(ab, subst)),
+            eq_token: ac.eq_token,
+            value: ac.value,
+        }),
+        GenericArgument::Constraint(co) => GenericArgument::Constraint(Constraint {
+            ident: co.ident,
+            generics: co.generics.map(|ab| ab_to_associated_ty(ab, subst)),
+            colon_token: co.colon_token,
+            bounds: bounds_to_associated_ty(co.bounds, subst),
+        }),
+        x @ GenericArgument::Const(_) => x,
+        x => abort!(
+            x,
+            "degeneric doesn't know how to handle this generic argument"
+        ),
     }
 }
 
 fn ab_to_associated_ty(
     ab: AngleBracketedGenericArguments,
-    generic_idents: &[&Ident],
+    subst: &Substitution,
 ) -> AngleBracketedGenericArguments {
     let args = ab
         .args
         .into_iter()
-        .map(|arg| arg_to_associated_ty(arg, generic_idents))
+        .map(|arg| arg_to_associated_ty(arg, subst))
         .collect();
     AngleBracketedGenericArguments {
         colon2_token: ab.colon2_token,
@@ -143,14 +201,14 @@ fn ab_to_associated_ty(
 }
 fn parenthesized_to_associated_ty(
     par: ParenthesizedGenericArguments,
-    generic_idents: &[&Ident],
+    subst: &Substitution,
 ) -> ParenthesizedGenericArguments {
     let inputs = par
         .inputs
         .into_iter()
-        .map(|ty| to_associated_ty(ty, generic_idents))
+        .map(|ty| to_associated_ty(ty, subst))
         .collect();
-    let output = return_type_to_associated_ty(par.output, generic_idents);
+    let output = return_type_to_associated_ty(par.output, subst);
     ParenthesizedGenericArguments {
         paren_token: par.paren_token,
         inputs,
@@ -158,13 +216,13 @@ fn parenthesized_to_associated_ty(
     }
 }
 
-fn path_segment_to_associated_ty(seg: PathSegment, generic_idents: &[&Ident]) -> PathSegment {
+fn path_segment_to_associated_ty(seg: PathSegment, subst: &Substitution) -> PathSegment {
     let arguments = match seg.arguments {
         PathArguments::AngleBracketed(ab) => {
-            PathArguments::AngleBracketed(ab_to_associated_ty(ab, generic_idents))
+            PathArguments::AngleBracketed(ab_to_associated_ty(ab, subst))
         }
         PathArguments::Parenthesized(par) => {
-            PathArguments::Parenthesized(parenthesized_to_associated_ty(par, generic_idents))
+            PathArguments::Parenthesized(parenthesized_to_associated_ty(par, subst))
         }
         x => x,
     };
@@ -173,11 +231,11 @@ fn path_segment_to_associated_ty(seg: PathSegment, generic_idents: &[&Ident]) ->
         arguments,
     }
 }
-fn nongeneric_path_to_associated_ty(path: Path, generic_idents: &[&Ident]) -> Path {
+fn path_to_associated_ty(path: Path, subst: &Substitution) -> Path {
     let segments = path
         .segments
         .into_iter()
-        .map(|seg| path_segment_to_associated_ty(seg, generic_idents))
+        .map(|seg| path_segment_to_associated_ty(seg, subst))
         .collect();
     Path {
         leading_colon: path.leading_colon,
@@ -185,62 +243,169 @@ fn nongeneric_path_to_associated_ty(path: Path, generic_idents: &[&Ident]) -> Pa
     }
 }
 
-fn path_to_associated_ty(path: Path, generic_idents: &[&Ident]) -> Path {
-    if path.segments.len() == 1 {
-        let first_segment = path.segments.first().unwrap();
-        if generic_idents.iter().any(|id| **id == first_segment.ident) {
-            let mut segments = Punctuated::<PathSegment, syn::Token![::]>::new();
-            segments.push(PathSegment {
-                ident: format_ident!("Self"),
-                arguments: PathArguments::None,
-            });
-            segments.push(first_segment.clone());
-            Path {
-                leading_colon: None,
-                segments,
-            }
-        } else {
-            nongeneric_path_to_associated_ty(path, generic_idents)
-        }
-    } else {
-        nongeneric_path_to_associated_ty(path, generic_idents)
-    }
-}
-
 use syn::TraitBound;
-pub fn bound_to_associated_ty(bound: TypeParamBound, generic_idents: &[&Ident]) -> TypeParamBound {
+pub fn bound_to_associated_ty(bound: TypeParamBound, subst: &Substitution) -> TypeParamBound {
     match bound {
         TypeParamBound::Trait(tr) => TypeParamBound::Trait(TraitBound {
             paren_token: tr.paren_token,
             modifier: tr.modifier,
             lifetimes: tr.lifetimes,
-            path: path_to_associated_ty(tr.path, generic_idents),
+            path: path_to_associated_ty(tr.path, subst),
         }),
         x => x,
     }
 }
 
-fn type_path_to_associated_ty(tp: TypePath, generic_idents: &[&Ident]) -> TypePath {
-    TypePath {
-        qself: tp.qself,
-        path: path_to_associated_ty(tp.path, generic_idents),
+pub fn predicate_to_associated_ty(pred: WherePredicate, subst: &Substitution) -> WherePredicate {
+    match pred {
+        WherePredicate::Type(pt) => WherePredicate::Type(PredicateType {
+            lifetimes: pt.lifetimes,
+            bounded_ty: to_associated_ty(pt.bounded_ty, subst),
+            colon_token: pt.colon_token,
+            bounds: pt
+                .bounds
+                .into_iter()
+                .map(|bound| bound_to_associated_ty(bound, subst))
+                .collect(),
+        }),
+        x => x,
+    }
+}
+
+fn type_path_to_associated_ty(tp: TypePath, subst: &Substitution) -> Type {
+    if let Some(qself) = tp.qself {
+        return Type::Path(TypePath {
+            qself: Some(QSelf {
+                ty: Box::new(to_associated_ty(*qself.ty, subst)),
+                ..qself
+            }),
+            path: path_to_associated_ty(tp.path, subst),
+        });
+    }
+
+    let generic = match tp.path.segments.first() {
+        Some(first)
+            if tp.path.leading_colon.is_none()
+                && matches!(first.arguments, PathArguments::None) =>
+        {
+            subst
+                .replacement(&first.ident)
+                .map(|replacement| (first.ident.clone(), replacement))
+        }
+        _ => None,
+    };
+
+    match generic {
+        None => Type::Path(TypePath {
+            qself: None,
+            path: path_to_associated_ty(tp.path, subst),
+        }),
+        Some((_, replacement)) if tp.path.segments.len() == 1 => replacement,
+        Some((ident, replacement)) => {
+            projection_to_associated_ty(tp.path, &ident, replacement, subst)
+        }
+    }
+}
+
+/// Std traits without associated types which are commonly combined with the trait a projection
+/// comes from, together with the module they live in.
+const WITHOUT_ASSOCIATED_TYPES: &[(&str, &str)] = &[
+    ("marker", "Send"),
+    ("marker", "Sync"),
+    ("marker", "Sized"),
+    ("marker", "Unpin"),
+    ("marker", "Copy"),
+    ("clone", "Clone"),
+    ("default", "Default"),
+    ("fmt", "Debug"),
+    ("fmt", "Display"),
+    ("hash", "Hash"),
+    ("cmp", "PartialEq"),
+    ("cmp", "Eq"),
+    ("cmp", "PartialOrd"),
+    ("cmp", "Ord"),
+];
+
+/// Whether `bound` is `?Sized` or one of [`WITHOUT_ASSOCIATED_TYPES`], written as `Debug`,
+/// `fmt::Debug` or `std::fmt::Debug` (or `core::`, `alloc::`, with or without a leading `::`).
+fn is_std_without_associated_types(bound: &TraitBound) -> bool {
+    if let TraitBoundModifier::Maybe(_) = bound.modifier {
+        return true;
+    }
+    let segments: Vec<_> = bound
+        .path
+        .segments
+        .iter()
+        .map(|seg| seg.ident.to_string())
+        .collect();
+    let segments: Vec<_> = segments.iter().map(String::as_str).collect();
+    WITHOUT_ASSOCIATED_TYPES
+        .iter()
+        .any(|&(module, name)| match segments.as_slice() {
+            [n] => bound.path.leading_colon.is_none() && *n == name,
+            [m, n] => bound.path.leading_colon.is_none() && *m == module && *n == name,
+            [krate, m, n] => ["std", "core", "alloc"].contains(krate) && *m == module && *n == name,
+            _ => false,
+        })
+}
+
+/// Turns `T::Assoc` into `<Self::T as Trait>::Assoc`, where `Trait` is the only trait bound of
+/// `T`. When `T` has several bounds, the std traits without associated types, such as `Send` or
+/// `Debug`, don't count. Rust can't resolve `Self::T::Assoc` on its own.
+fn projection_to_associated_ty(
+    path: Path,
+    ident: &Ident,
+    replacement: Type,
+    subst: &Substitution,
+) -> Type {
+    let span = path.span();
+    if path.segments.len() > 2 {
+        abort!(
+            span,
+            "degeneric can't rewrite nested projections of `{}`", ident;
+            help = "write the projection using the qualified `<{} as Trait>::Assoc` syntax", ident
+        );
+    }
+
+    let assoc = path
+        .segments
+        .into_iter()
+        .nth(1)
+        .map(|seg| path_segment_to_associated_ty(seg, subst))
+        .unwrap();
+    let mut bounds = subst.trait_bounds(ident);
+    if bounds.len() > 1 {
+        bounds.retain(|bound| !is_std_without_associated_types(bound));
+    }
+    match bounds.as_slice() {
+        [bound] => {
+            let trait_path =
+                path_to_associated_ty(bound.path.clone(), &subst.without_bounds_of(ident));
+            parse_quote! { <#replacement as #trait_path>::#assoc }
+        }
+        _ => abort!(
+            span,
+            "ambiguous associated type `{}::{}`", ident, assoc.ident;
+            help = "write it as `<{} as Trait>::{}`", ident, assoc.ident
+        ),
     }
 }
 
-pub fn to_associated_ty(ty: Type, generic_idents: &[&Ident]) -> Type {
+pub fn to_associated_ty(ty: Type, subst: &Substitution) -> Type {
     use Type::*;
     match ty {
-        Array(ta) => Array(array_to_associated_ty(ta, generic_idents)),
-        BareFn(bft) => BareFn(bare_fn_to_associated_ty(bft, generic_idents)),
-        Group(gr) => Group(group_to_associated_ty(gr, generic_idents)),
-        Paren(pa) => Paren(paren_to_associated_ty(pa, generic_idents)),
-        Path(tp) => Path(type_path_to_associated_ty(tp, generic_idents)),
-        Ptr(pt) => Ptr(ptr_to_associated_ty(pt, generic_idents)),
-        Reference(tr) => Reference(reference_to_associated_ty(tr, generic_idents)),
-        Slice(sl) => Slice(slice_to_associated_ty(sl, generic_idents)),
-        Tuple(tup) => Tuple(tuple_to_associated_ty(tup, generic_idents)),
-        _ => {
-            unimplemented!("")
-        }
+        Array(ta) => Array(array_to_associated_ty(ta, subst)),
+        BareFn(bft) => BareFn(bare_fn_to_associated_ty(bft, subst)),
+        Group(gr) => Group(group_to_associated_ty(gr, subst)),
+        Paren(pa) => Paren(paren_to_associated_ty(pa, subst)),
+        Path(tp) => type_path_to_associated_ty(tp, subst),
+        Ptr(pt) => Ptr(ptr_to_associated_ty(pt, subst)),
+        Reference(tr) => Reference(reference_to_associated_ty(tr, subst)),
+        Slice(sl) => Slice(slice_to_associated_ty(sl, subst)),
+        Tuple(tup) => Tuple(tuple_to_associated_ty(tup, subst)),
+        TraitObject(to) => TraitObject(trait_object_to_associated_ty(to, subst)),
+        ImplTrait(it) => ImplTrait(impl_trait_to_associated_ty(it, subst)),
+        x @ Never(_) | x @ Infer(_) => x,
+        x => abort!(x, "degeneric doesn't know how to handle this type"),
     }
 }
@@ -0,0 +1,114 @@
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::Ident;
+
+fn mentioned_in_stream<'i>(ts: TokenStream, idents: &[&'i Ident]) -> Option<&'i Ident> {
+    let mut after_path_sep = false;
+    let mut colons = 0;
+    for tt in ts {
+        match tt {
+            TokenTree::Ident(ref id) if !after_path_sep => {
+                if let Some(found) = idents.iter().find(|ident| **ident == id) {
+                    return Some(found);
+                }
+            }
+            TokenTree::Group(ref gr) => {
+                if let Some(found) = mentioned_in_stream(gr.stream(), idents) {
+                    return Some(found);
+                }
+            }
+            _ => {}
+        }
+        // `Other::N` or `value.N` refers to something else than the generic parameter `N`
+        colons = match &tt {
+            TokenTree::Punct(p) if p.as_char() == ':' => colons + 1,
+            _ => 0,
+        };
+        after_path_sep = colons == 2 || matches!(&tt, TokenTree::Punct(p) if p.as_char() == '.');
+    }
+    None
+}
+
+/// Finds the first of `idents` which is used by `tokens`, e.g. `N` in `[T; N]`.
+pub fn mentioned_ident<'i>(tokens: impl ToTokens, idents: &[&'i Ident]) -> Option<&'i Ident> {
+    mentioned_in_stream(tokens.into_token_stream(), idents)
+}
+
+fn renamed_in_stream(ts: TokenStream, from: &Ident, to: &Ident) -> TokenStream {
+    let mut after_path_sep = false;
+    let mut colons = 0;
+    ts.into_iter()
+        .map(|tt| {
+            let renamed = match tt {
+                TokenTree::Ident(ref id) if !after_path_sep && id == from => {
+                    TokenTree::Ident(to.clone())
+                }
+                TokenTree::Group(ref gr) => {
+                    let mut group =
+                        Group::new(gr.delimiter(), renamed_in_stream(gr.stream(), from, to));
+                    group.set_span(gr.span());
+                    TokenTree::Group(group)
+                }
+                ref other => other.clone(),
+            };
+            colons = match &tt {
+                TokenTree::Punct(p) if p.as_char() == ':' => colons + 1,
+                _ => 0,
+            };
+            after_path_sep =
+                colons == 2 || matches!(&tt, TokenTree::Punct(p) if p.as_char() == '.');
+            renamed
+        })
+        .collect()
+}
+
+/// Replaces uses of the `from` generic parameter in `tokens` with `to`.
+pub fn renamed_ident(tokens: impl ToTokens, from: &Ident, to: &Ident) -> TokenStream {
+    renamed_in_stream(tokens.into_token_stream(), from, to)
+}
+
+/// All of `idents` which are used by `tokens`, in the order of `idents`.
+pub fn mentioned_idents<'i>(tokens: impl ToTokens, idents: &[&'i Ident]) -> Vec<&'i Ident> {
+    let ts = tokens.into_token_stream();
+    idents
+        .iter()
+        .copied()
+        .filter(|ident| mentioned_in_stream(ts.clone(), &[ident]).is_some())
+        .collect()
+}
+
+/// Replaces every `from` placeholder in `tokens` with arbitrary tokens, e.g. `$inner` in the body
+/// of a `macro_rules!`.
+pub fn replaced_ident(tokens: impl ToTokens, from: &Ident, to: &TokenStream) -> TokenStream {
+    tokens
+        .into_token_stream()
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Ident(ref id) if id == from => to.clone(),
+            TokenTree::Group(ref gr) => {
+                let mut group = Group::new(gr.delimiter(), replaced_ident(gr.stream(), from, to));
+                group.set_span(gr.span());
+                TokenTree::Group(group).into_token_stream()
+            }
+            other => other.into_token_stream(),
+        })
+        .collect()
+}
+
+/// Finds a lifetime other than `'static` used by `tokens`.
+pub fn mentioned_lifetime(tokens: impl ToTokens) -> Option<Ident> {
+    let mut after_apostrophe = false;
+    for tt in tokens.into_token_stream() {
+        match &tt {
+            TokenTree::Ident(id) if after_apostrophe && id != "static" => return Some(id.clone()),
+            TokenTree::Group(gr) => {
+                if let Some(found) = mentioned_lifetime(gr.stream()) {
+                    return Some(found);
+                }
+            }
+            _ => {}
+        }
+        after_apostrophe = matches!(&tt, TokenTree::Punct(p) if p.as_char() == '\'');
+    }
+    None
+}
@@ -1,5 +1,9 @@
 mod associated;
+mod mentions;
 mod references;
+mod substitution;
 
 pub use self::associated::*;
+pub use self::mentions::*;
 pub use self::references::*;
+pub use self::substitution::*;
@@ -14,11 +14,26 @@ pub fn can_be_made_mutable(ty: &Type) -> bool {
     }
 }
 
+fn reference_to_paren(ty: Type, mutability: Option<Token![mut]>) -> Type {
+    Type::Reference(TypeReference {
+        and_token: Default::default(),
+        lifetime: None,
+        mutability,
+        elem: Box::new(Type::Paren(TypeParen {
+            paren_token: Default::default(),
+            elem: Box::new(ty),
+        })),
+    })
+}
+
 pub fn make_reference(ty: Type, mutability: Option<Token![mut]>) -> Result<Type, Error> {
     match ty {
         Type::Reference(rf) => Ok(Type::Reference(TypeReference { mutability, ..rf })),
         x @ Type::Never(_) => Ok(x),
         Type::Ptr(ptr) => Ok(Type::Ptr(TypePtr { mutability, ..ptr })),
+        // `&dyn A + B` is ambiguous, it has to be `&(dyn A + B)`
+        Type::TraitObject(ref to) if to.bounds.len() > 1 => Ok(reference_to_paren(ty, mutability)),
+        Type::ImplTrait(ref it) if it.bounds.len() > 1 => Ok(reference_to_paren(ty, mutability)),
         Type::Paren(tp) => Ok(Type::Paren(TypeParen {
             elem: Box::new(make_reference(*tp.elem, mutability)?),
             ..tp
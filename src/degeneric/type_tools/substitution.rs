@@ -0,0 +1,115 @@
+use syn::{parse_quote, Ident, Path, Type, TypeParamBound};
+
+#[derive(Clone)]
+struct SubstitutedParam {
+    ident: Ident,
+    bounds: Vec<TypeParamBound>,
+    replacement: Option<Type>,
+    qualifier: Option<Path>,
+}
+
+/// Describes which generic parameters [`super::to_associated_ty`] turns into associated types
+/// and where it takes the associated types from.
+#[derive(Clone)]
+pub struct Substitution {
+    params: Vec<SubstitutedParam>,
+    owner: Type,
+    owner_trait: Option<Path>,
+}
+
+impl Substitution {
+    /// Substitutes the `params` with associated types of `Self`. The bounds of the parameters are
+    /// used to qualify projections such as `T::Item`.
+    pub fn new(params: impl IntoIterator<Item = (Ident, Vec<TypeParamBound>)>) -> Self {
+        Self {
+            params: params
+                .into_iter()
+                .map(|(ident, bounds)| SubstitutedParam {
+                    ident,
+                    bounds,
+                    replacement: None,
+                    qualifier: None,
+                })
+                .collect(),
+            owner: parse_quote! { Self },
+            owner_trait: None,
+        }
+    }
+
+    /// Same substitution which takes the associated types from `owner` instead of `Self`. When
+    /// `owner_trait` is given, the associated types are qualified as `<owner as owner_trait>::X`.
+    pub fn with_owner(&self, owner: Type, owner_trait: Option<Path>) -> Self {
+        Self {
+            params: self.params.clone(),
+            owner,
+            owner_trait,
+        }
+    }
+
+    /// Same substitution which replaces the `ident` parameter with `ty` instead of an associated
+    /// type.
+    pub fn with_replacement(&self, ident: &Ident, ty: Type) -> Self {
+        let mut cl = self.clone();
+        for param in cl.params.iter_mut().filter(|param| &param.ident == ident) {
+            param.replacement = Some(ty.clone());
+        }
+        cl
+    }
+
+    /// Same substitution which takes the `ident` associated type from the `qualifier` trait of
+    /// the owner, e.g. `<Self as LoggingDeps>::Logger`.
+    pub fn with_qualifier(&self, ident: &Ident, qualifier: Path) -> Self {
+        let mut cl = self.clone();
+        for param in cl.params.iter_mut().filter(|param| &param.ident == ident) {
+            param.qualifier = Some(qualifier.clone());
+        }
+        cl
+    }
+
+    pub fn contains(&self, ident: &Ident) -> bool {
+        self.params.iter().any(|param| &param.ident == ident)
+    }
+
+    /// Type which replaces the `ident` generic parameter.
+    pub fn replacement(&self, ident: &Ident) -> Option<Type> {
+        let owner = &self.owner;
+        self.params
+            .iter()
+            .find(|param| &param.ident == ident)
+            .map(|param| {
+                let ident = &param.ident;
+                if let Some(replacement) = &param.replacement {
+                    return replacement.clone();
+                }
+                match param.qualifier.as_ref().or(self.owner_trait.as_ref()) {
+                    Some(owner_trait) => parse_quote! { <#owner as #owner_trait>::#ident },
+                    None => parse_quote! { #owner::#ident },
+                }
+            })
+    }
+
+    /// Trait bounds of the `ident` generic parameter.
+    pub fn trait_bounds(&self, ident: &Ident) -> Vec<&syn::TraitBound> {
+        self.params
+            .iter()
+            .filter(|param| &param.ident == ident)
+            .flat_map(|param| &param.bounds)
+            .flat_map(|bound| match bound {
+                TypeParamBound::Trait(tb) if tb.modifier == syn::TraitBoundModifier::None => {
+                    Some(tb)
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Same substitution without knowledge of bounds of `ident`. This is used while rewriting the
+    /// bounds themselves.
+    pub fn without_bounds_of(&self, ident: &Ident) -> Self {
+        let mut cl = self.clone();
+        for param in cl.params.iter_mut().filter(|param| &param.ident == ident) {
+            param.bounds.clear();
+        }
+        cl
+    }
+}
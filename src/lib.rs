@@ -298,6 +298,1029 @@
 //!
 //! ```
 //!
+//! Predicates which don't bound a single type parameter, such as `Vec<T>: Debug` or
+//! `S::Error: Error`, are placed into the where clause of the generated trait. Rust lets generic
+//! code rely on predicates about associated types of the trait (`S::Error` here). Other
+//! predicates, like `Vec<T>: Debug`, need to be repeated by the generic code.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::error::Error;
+//! use std::fmt::Debug;
+//!
+//! pub trait Service {
+//!     type Error;
+//! }
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! struct Container<S: Service, T>
+//! where
+//!     S::Error: Error,
+//!     Vec<T>: Debug,
+//! {
+//!     service: S,
+//!     items: Vec<T>,
+//! }
+//!
+//! fn describe_error<C: ContainerTrait>(_c: &C, e: &<C::S as Service>::Error) -> String
+//! where
+//!     Vec<C::T>: Debug,
+//! {
+//!     e.to_string()
+//! }
+//!
+//! struct Http;
+//!
+//! impl Service for Http {
+//!     type Error = std::fmt::Error;
+//! }
+//!
+//! let c = Container {
+//!     service: Http,
+//!     items: vec![1, 2, 3],
+//! };
+//!
+//! assert_eq!(describe_error(&c, &std::fmt::Error), "an error occurred when formatting an argument");
+//! ```
+//!
+//! # Preserving generics
+//!
+//! Sometimes, there's a type which the caller really chooses. Marking a type parameter with
+//! `#[degeneric(preserve)]` keeps it as a generic parameter of the generated trait instead of
+//! turning it into an associated type.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! struct Container<#[degeneric(preserve)] P: Debug, L> where P: Clone {
+//!     payload: P,
+//!     logger: L,
+//! }
+//!
+//! let c = Container {
+//!     payload: 42,
+//!     logger: String::from("logger"),
+//! };
+//!
+//! fn print_payload<P: Debug + Clone>(c: &impl ContainerTrait<P>) -> String {
+//!     format!("{:?}", c.payload())
+//! }
+//!
+//! assert_eq!(print_payload(&c), "42");
+//! ```
+//!
+//! # Supertraits and where clause on the trait
+//!
+//! `trait_decl` accepts a whole trait header. Supertraits, `unsafe`, additional generics and a
+//! where clause are placed on both the trait declaration and its implementation. Type parameters
+//! of the struct are referred to by their name and degeneric turns them into associated types.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric, Debug)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait: Debug + Send + Sync + 'static where T: Clone")]
+//! struct Container<T: Debug + Send + Sync + 'static> {
+//!     item: T,
+//! }
+//!
+//! let c = Container {
+//!     item: 42,
+//! };
+//!
+//! fn spawn_with(c: impl ContainerTrait) -> String {
+//!     std::thread::spawn(move || format!("{:?}", c)).join().unwrap()
+//! }
+//!
+//! assert_eq!(spawn_with(c), "Container { item: 42 }");
+//! ```
+//!
+//! # Tuple structs
+//!
+//! Getters of unnamed fields are called `_0`, `_1`, ... and `_0_mut`, `_1_mut`, ... The
+//! `#[degeneric(name = "...")]` attribute gives the getters a better name.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ServicesTrait")]
+//! struct Services<L, H>(#[degeneric(name = "logger")] L, H);
+//!
+//! let s = Services(String::from("logger"), 42);
+//!
+//! fn accept_services(mut s: impl ServicesTrait<L = String, H = i32>) {
+//!     s.logger_mut().push_str(" and more");
+//!     assert_eq!(s.logger(), "logger and more");
+//!     assert_eq!(s._1(), &42);
+//! }
+//!
+//! accept_services(s);
+//! ```
+//!
+//! # Const generics
+//!
+//! Const parameters become associated consts of the generated trait. Rust doesn't allow using
+//! associated consts in types, so fields whose type mentions such a const need either a
+//! `#[degeneric(no_getter)]`, or the const parameter needs to be preserved.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait BufferTrait")]
+//! struct Buffer<T, const N: usize, #[degeneric(preserve)] const M: usize> {
+//!     len: usize,
+//!     items: [T; M],
+//!     #[degeneric(no_getter)]
+//!     reserve: [T; N],
+//! }
+//!
+//! let b = Buffer {
+//!     len: 2,
+//!     items: [1, 2, 3, 4],
+//!     reserve: [0; 8],
+//! };
+//!
+//! fn capacity<B: BufferTrait<4>>(b: &B) -> usize {
+//!     b.items().len() + B::N
+//! }
+//!
+//! assert_eq!(capacity(&b), 12);
+//! ```
+//!
+//! ```compile_fail
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait BufferTrait")]
+//! struct Buffer<T, const N: usize> {
+//!     // ERROR: N isn't preserved and the field has a getter
+//!     items: [T; N],
+//! }
+//! ```
+//!
+//! # Trait objects
+//!
+//! Generics used in bounds of `dyn Trait` and `impl Trait` types are turned into associated
+//! types, too.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait HandlerTrait")]
+//! struct Handler<T, R> {
+//!     handler: Box<dyn Fn(T) -> R + Send>,
+//! }
+//!
+//! let h = Handler {
+//!     handler: Box::new(|x: u8| u16::from(x) * 2),
+//! };
+//!
+//! fn run(h: &impl HandlerTrait<T = u8, R = u16>) -> u16 {
+//!     (h.handler())(21)
+//! }
+//!
+//! assert_eq!(run(&h), 42);
+//! ```
+//!
+//! An unsized trait object can be the last field, too. Its getter returns
+//! `&(dyn Fn(Self::T) + Send)`.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait HandlerTrait")]
+//! struct Handler<T> {
+//!     calls: u8,
+//!     handler: dyn Fn(T) + Send,
+//! }
+//!
+//! fn run(h: &(impl HandlerTrait<T = u8> + ?Sized)) {
+//!     (h.handler())(*h.calls())
+//! }
+//! ```
+//!
+//! # Associated type bindings and const arguments
+//!
+//! Generics are found in associated type bindings, such as `Iterator<Item = T>`, in bounds on
+//! associated types and next to const arguments.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::future::Future;
+//! use std::pin::Pin;
+//!
+//! pub struct Chunk<T, const N: usize>([T; N]);
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait StreamTrait")]
+//! struct Stream<T, S: Iterator<Item: Into<T>>> {
+//!     items: Box<dyn Iterator<Item = T>>,
+//!     done: Pin<Box<dyn Future<Output = T>>>,
+//!     chunks: Vec<Chunk<T, 16>>,
+//!     source: S,
+//! }
+//!
+//! let s = Stream {
+//!     items: Box::new(vec![1, 2, 3].into_iter()),
+//!     done: Box::pin(async { 0 }),
+//!     chunks: vec![],
+//!     source: std::iter::once(4_u8),
+//! };
+//!
+//! fn sum(mut s: impl StreamTrait<T = i32>) -> i32 {
+//!     s.items_mut().sum()
+//! }
+//!
+//! assert_eq!(sum(s), 6);
+//! ```
+//!
+//! # Projections
+//!
+//! Fields may use associated types of the generic parameters. A shorthand such as `S::Error`
+//! is qualified by the only trait bound of `S`, i.e. `<Self::S as Service>::Error`. When `S` has
+//! several trait bounds, the std traits without associated types are left out: `Send`, `Sync`,
+//! `Sized`, `?Sized`, `Unpin`, `Copy`, `Clone`, `Default`, `Debug`, `Display`, `Hash`,
+//! `PartialEq`, `Eq`, `PartialOrd` and `Ord`. They're recognized by name, written bare, as
+//! `fmt::Debug` or as `std::fmt::Debug`, so a trait of yours with one of these names counts as
+//! the std one in that case. If more than one bound is left, write the projection as
+//! `<S as Service>::Error` instead.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! pub trait Service {
+//!     type Response;
+//!     type Error;
+//! }
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! struct Container<S: Service, C: Service + Clone> {
+//!     service: S,
+//!     last_error: Option<S::Error>,
+//!     client: C,
+//!     responses: Vec<<C as Service>::Response>,
+//! }
+//!
+//! #[derive(Clone)]
+//! struct Http;
+//!
+//! impl Service for Http {
+//!     type Response = u16;
+//!     type Error = String;
+//! }
+//!
+//! let c = Container {
+//!     service: Http,
+//!     last_error: Some(String::from("timeout")),
+//!     client: Http,
+//!     responses: vec![200, 404],
+//! };
+//!
+//! fn report<C: ContainerTrait>(c: &C) -> usize
+//! where
+//!     <C::S as Service>::Error: AsRef<str>,
+//! {
+//!     c.last_error().as_ref().map_or(0, |e| e.as_ref().len()) + c.responses().len()
+//! }
+//!
+//! assert_eq!(report(&c), 9);
+//! ```
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! pub trait Service {
+//!     type Error;
+//! }
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! struct Container<S: Service + Send + Sync> {
+//!     service: S,
+//!     last_error: Option<S::Error>,
+//! }
+//!
+//! struct Http;
+//!
+//! impl Service for Http {
+//!     type Error = String;
+//! }
+//!
+//! fn failed(c: &impl ContainerTrait) -> bool {
+//!     c.last_error().is_some()
+//! }
+//!
+//! assert!(failed(&Container { service: Http, last_error: Some(String::from("timeout")) }));
+//! ```
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait QueueTrait")]
+//! struct Queue<I: Iterator + Debug> {
+//!     items: I,
+//!     peeked: Option<I::Item>,
+//! }
+//!
+//! let q = Queue { items: 1..3, peeked: Some(0) };
+//! assert_eq!(q.peeked(), &Some(0));
+//! ```
+//!
+//! # Read-only trait and mutable extension trait
+//!
+//! A trait with `_mut` getters can't be implemented for shared references or smart pointers.
+//! With `split_mut`, the associated types and getters go to the trait from `trait_decl` and the
+//! `_mut` getters go to a second trait, which extends the first one. The read-only trait is then
+//! also implemented for `&C`, `&mut C`, `Box<C>`, `Rc<C>` and `Arc<C>`.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::sync::Arc;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! #[degeneric(split_mut = "ContainerTraitMut")]
+//! struct Container<Logger> {
+//!     logger: Logger,
+//!     retries: u32,
+//! }
+//!
+//! fn retries(c: impl ContainerTrait) -> u32 {
+//!     *c.retries()
+//! }
+//!
+//! fn retry_more<C: ContainerTraitMut>(c: &mut C) {
+//!     *c.retries_mut() += 1;
+//! }
+//!
+//! let mut c = Container { logger: (), retries: 2 };
+//! retry_more(&mut c);
+//! assert_eq!(retries(&c), 3);
+//!
+//! let shared = Arc::new(c);
+//! assert_eq!(retries(shared.clone()), 3);
+//! ```
+//!
+//! # Borrowing several fields mutably at once
+//!
+//! Mutable getters borrow the whole container, so `c.logger_mut()` and `c.client_mut()` can't be
+//! held at the same time. `mut_parts` generates a struct with a public field for every getter
+//! and a trait method `as_mut_parts` which fills it in. Fields which can't be made mutable are
+//! copied into the struct as they are.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! #[degeneric(mut_parts = "ContainerMutParts")]
+//! struct Container<'a, Logger, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     name: &'a str,
+//! }
+//!
+//! fn send<'a>(c: &mut impl ContainerTrait<'a, Logger = Vec<String>, Client = Vec<u8>>) {
+//!     let ContainerMutParts { logger, client, name, .. } = c.as_mut_parts();
+//!     client.push(42);
+//!     logger.push(format!("{} sent {} bytes", name, client.len()));
+//! }
+//!
+//! let mut c = Container { logger: vec![], client: vec![], name: "app" };
+//! send(&mut c);
+//! assert_eq!(c.logger, vec!["app sent 1 bytes"]);
+//! ```
+//!
+//! # Borrowed view of all fields
+//!
+//! `view` generates a struct which borrows every field that has a getter, together with a
+//! provided trait method `view` returning it. Generic code can then destructure all dependencies
+//! in one statement.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", view = "ContainerRef")]
+//! struct Container<Logger, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     retries: u32,
+//! }
+//!
+//! fn describe(c: &impl ContainerTrait<Logger = &'static str, Client = String>) -> String {
+//!     let ContainerRef { logger, client, retries, .. } = c.view();
+//!     format!("{} {} {}", logger, client, retries)
+//! }
+//!
+//! let c = Container { logger: "stdout", client: String::from("http"), retries: 3 };
+//! assert_eq!(describe(&c), "stdout http 3");
+//! ```
+//!
+//! # Moving fields out of the container
+//!
+//! With `into_parts`, the trait gets `into_parts`, which returns all fields with a getter as a
+//! tuple, and `into_<field>` for every such field. These methods require `Self: Sized`, so the
+//! rest of the trait stays usable behind references and trait objects.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", into_parts)]
+//! struct Container<Logger, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     retries: u32,
+//! }
+//!
+//! fn spawn<C: ContainerTrait>(c: C) -> std::thread::JoinHandle<()>
+//! where
+//!     C::Client: Send + 'static,
+//! {
+//!     let client = c.into_client();
+//!     std::thread::spawn(move || drop(client))
+//! }
+//!
+//! let c = Container { logger: (), client: String::from("http"), retries: 3 };
+//! let (logger, client, retries) = Container { logger: (), client: 1u8, retries: 2 }.into_parts();
+//! assert_eq!((logger, client, retries), ((), 1, 2));
+//! spawn(c).join().unwrap();
+//! ```
+//!
+//! Methods named after the fields keep their leading underscores, so `client` and `_client` get
+//! `into_client` and `into__client`. A field whose method clashes with another generated method,
+//! such as `parts` with `into_parts`, has to be renamed.
+//!
+//! ```compile_fail
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", into_parts)]
+//! struct Container<Parts> {
+//!     parts: Parts,
+//! }
+//! ```
+//!
+//! # Setters
+//!
+//! `setters` on the struct or on single fields generates `set_<field>`, `replace_<field>` and
+//! `take_<field>`. Fields which can't be made mutable get only the setter, which swaps the
+//! reference. `take_<field>` requires the type to implement `Default`, so it's only generated
+//! for fields whose type depends on a generic parameter.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! struct Container<'a, Logger> {
+//!     #[degeneric(setters)]
+//!     logger: Logger,
+//!     #[degeneric(setters)]
+//!     name: &'a str,
+//!     retries: u32,
+//! }
+//!
+//! fn reconfigure<'a>(c: &mut impl ContainerTrait<'a, Logger = Vec<String>>) -> Vec<String> {
+//!     c.set_name("reconfigured");
+//!     let old = c.replace_logger(vec![String::from("new")]);
+//!     c.take_logger();
+//!     old
+//! }
+//!
+//! let mut c = Container { logger: vec![String::from("old")], name: "app", retries: 0 };
+//! assert_eq!(reconfigure(&mut c), vec!["old"]);
+//! assert!(c.logger.is_empty());
+//! assert_eq!(c.name, "reconfigured");
+//! ```
+//!
+//! # Swapping dependencies for other types
+//!
+//! `with` generates inherent methods which replace the fields of one type parameter with values
+//! of another type. The method is called after the field if only one field uses the parameter,
+//! otherwise it's called after the parameter and takes all of those fields. The new type has
+//! to satisfy the same bounds as the parameter it replaces.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Display;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", with)]
+//! struct Container<Logger: Display, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     fallback: Client,
+//! }
+//!
+//! let c = Container { logger: "stdout", client: "http", fallback: "file" };
+//! let c = c.with_logger(404).with_client(1u8, 2u8);
+//! assert_eq!((c.logger, c.client, c.fallback), (404, 1, 2));
+//! ```
+//!
+//! A parameter which appears in the bounds of another parameter, such as `T` in
+//! `A: Fn() -> T`, can't be replaced on its own, so it doesn't get a method.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::marker::PhantomData;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", with)]
+//! struct Container<T: Default, A: Fn() -> T> {
+//!     a: A,
+//!     _t: PhantomData<T>,
+//! }
+//!
+//! let c = Container { a: || 1u8, _t: PhantomData };
+//! let c = c.with_a(|| 2u8);
+//! assert_eq!((c.a)(), 2);
+//! ```
+//!
+//! # Transforming dependencies
+//!
+//! `map` generates inherent `map_<field>` methods, which transform the only field using a type
+//! parameter with a closure. That's handy for wrapping a dependency in a decorator. If several
+//! fields use the parameter, pick the one to transform with `#[degeneric(map = "field")]` on
+//! the parameter. The method takes the new values of the other ones as arguments, only
+//! `PhantomData` markers are filled in on their own.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::marker::PhantomData;
+//!
+//! struct Retry<C>(C, u32);
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", map)]
+//! struct Container<Logger, #[degeneric(map = "client")] Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     fallback: Option<Client>,
+//!     _client: PhantomData<Client>,
+//! }
+//!
+//! let c = Container { logger: "stdout", client: "http", fallback: None, _client: PhantomData };
+//! let c = c.map_client(|client| Retry(client, 3), Some(Retry("https", 1)));
+//! assert_eq!((c.client.0, c.client.1), ("http", 3));
+//! assert_eq!(c.fallback.map(|f| f.0), Some("https"));
+//! ```
+//!
+//! The arguments are named after the fields, whatever their names are.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait PairTrait", map)]
+//! struct Pair<#[degeneric(map = "g")] X> {
+//!     f: X,
+//!     g: X,
+//! }
+//!
+//! let p = Pair { f: 1u8, g: 2u8 }.map_g(|g| u16::from(g) * 10, 5);
+//! assert_eq!((p.f, p.g), (5, 20));
+//! ```
+//!
+//! Like with `with`, a parameter which appears in the bounds of another parameter doesn't get a
+//! method, and picking a field of such a parameter is an error.
+//!
+//! ```compile_fail
+//! use degeneric_macros::Degeneric;
+//! use std::marker::PhantomData;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", map)]
+//! struct Container<#[degeneric(map = "_t")] T: Default, A: Fn() -> T> {
+//!     a: A,
+//!     _t: PhantomData<T>,
+//! }
+//! ```
+//!
+//! # Constructor
+//!
+//! `constructor` adds `fn new(...) -> Self` to the trait, so generic code can build new
+//! containers of the same type. It takes all fields as arguments except those marked with
+//! `#[degeneric(default)]`, which are filled in with `Default::default()`.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::marker::PhantomData;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", constructor)]
+//! struct Container<Logger, Client, T> {
+//!     logger: Logger,
+//!     client: Client,
+//!     #[degeneric(default)]
+//!     _t: PhantomData<T>,
+//! }
+//!
+//! fn fresh_copy<C: ContainerTrait>(c: &C) -> C
+//! where
+//!     C::Logger: Clone,
+//!     C::Client: Clone,
+//! {
+//!     C::new(c.logger().clone(), c.client().clone())
+//! }
+//!
+//! let c: Container<_, _, ()> = Container::new("stdout", "http");
+//! assert_eq!(fresh_copy(&c).client, "http");
+//! ```
+//!
+//! # Recovering the concrete struct
+//!
+//! `concrete` generates a type alias which names the struct of an implementor, e.g.
+//! `ContainerOf<C>` is `Container<C::Logger, C::Client>`. The trait gets `as_concrete` and
+//! `into_concrete`, so generic code can use inherent methods of the struct or match its fields.
+//! Const parameters have to be preserved for this to work.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", concrete = "ContainerOf")]
+//! pub struct Container<Logger, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//! }
+//!
+//! impl<Logger, Client> Container<Logger, Client> {
+//!     fn dependencies(&self) -> usize {
+//!         2
+//!     }
+//! }
+//!
+//! fn unwrap<C: ContainerTrait>(c: C) -> (C::Logger, C::Client) {
+//!     assert_eq!(c.as_concrete().dependencies(), 2);
+//!     let ContainerOf::<C> { logger, client } = c.into_concrete();
+//!     (logger, client)
+//! }
+//!
+//! let c = Container { logger: "stdout", client: "http" };
+//! assert_eq!(unwrap(c), ("stdout", "http"));
+//! ```
+//!
+//! # Bundling the type parameters
+//!
+//! The derive leaves the struct generic over every dependency. The `bundle` attribute macro
+//! collects the type parameters into a single one, which implements a generated trait carrying
+//! the bounds of the parameters. Lifetimes, const parameters and parameters marked with
+//! `#[degeneric(preserve)]` stay on the struct. A marker type implementing the generated trait,
+//! e.g. `ContainerTypesOf<L, C>`, names concrete sets of types. The attribute has to come before
+//! `#[derive(Degeneric)]`, which then hides the single parameter as usual.
+//!
+//! ```
+//! use degeneric_macros::{bundle, Degeneric};
+//! use std::fmt::Debug;
+//!
+//! #[bundle(types = "ContainerTypes")]
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! pub struct Container<Logger: Debug, Client: Clone> {
+//!     logger: Logger,
+//!     client: Client,
+//! }
+//!
+//! type Prod = ContainerTypesOf<String, u16>;
+//!
+//! fn port<T: ContainerTypes>(c: &Container<T>) -> T::Client {
+//!     c.client.clone()
+//! }
+//!
+//! let c: Container<Prod> = Container { logger: String::from("stdout"), client: 8080 };
+//! assert_eq!(port(&c), 8080);
+//! assert_eq!(format!("{:?}", c.logger()), "\"stdout\"");
+//! ```
+//!
+//! # Profiles
+//!
+//! A profile names a concrete wiring of the container. Every
+//! `#[degeneric(profile(name = "Prod", Param = "Type", ...))]` becomes a type alias such as
+//! `ProdContainer`. The profile has to assign all type parameters which aren't preserved, and
+//! the compiler checks that the assigned types satisfy the bounds of the parameters. Lifetimes,
+//! const parameters and preserved parameters stay parameters of the alias.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! #[degeneric(profile(name = "Prod", Logger = "String", Client = "u16"))]
+//! #[degeneric(profile(name = "Test", Logger = "Vec<String>", Client = "()"))]
+//! pub struct Container<Logger: Debug, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//! }
+//!
+//! let prod: ProdContainer = Container { logger: String::from("stdout"), client: 8080 };
+//! let test: TestContainer = Container { logger: vec![], client: () };
+//! assert_eq!(*prod.client(), 8080);
+//! assert!(test.logger().is_empty());
+//! ```
+//!
+//! ```compile_fail
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! struct NotDebug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait")]
+//! // ERROR: NotDebug doesn't implement Debug
+//! #[degeneric(profile(name = "Prod", Logger = "NotDebug"))]
+//! pub struct Container<Logger: Debug> {
+//!     logger: Logger,
+//! }
+//! ```
+//!
+//! # Trait groups
+//!
+//! Functions often need only a few of the dependencies. Declare a group with
+//! `#[degeneric(trait_group(name = "LoggingDeps", vis = "pub"))]` on the struct and put fields
+//! into it with `#[degeneric(group = "LoggingDeps")]`. Every group becomes a trait with the
+//! getters of its fields and only the associated types these fields need. A field may belong to
+//! several groups.
+//!
+//! With `group_supertraits`, the `trait_decl` trait extends all the groups instead of repeating
+//! their associated types and getters. Every associated type is then declared by the first group
+//! which needs it. `group_supertraits` can't be combined with `split_mut`.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", group_supertraits)]
+//! #[degeneric(trait_group(name = "LoggingDeps", vis = "pub"))]
+//! #[degeneric(trait_group(name = "DbDeps", vis = "pub"))]
+//! pub struct Container<Logger: Debug, Db: Debug> {
+//!     #[degeneric(group = "LoggingDeps")]
+//!     logger: Logger,
+//!     #[degeneric(group = "DbDeps")]
+//!     db: Db,
+//!     name: String,
+//! }
+//!
+//! fn log(deps: &impl LoggingDeps) -> String {
+//!     format!("{:?}", deps.logger())
+//! }
+//!
+//! fn query(deps: &mut impl DbDeps) -> String {
+//!     format!("{:?}", deps.db_mut())
+//! }
+//!
+//! fn handle(c: &mut impl ContainerTrait) -> String {
+//!     let queried = query(c);
+//!     format!("{}: {} {}", c.name(), log(c), queried)
+//! }
+//!
+//! let mut c = Container { logger: "stdout", db: 5432, name: String::from("app") };
+//! assert_eq!(handle(&mut c), "app: \"stdout\" 5432");
+//! ```
+//!
+//! # Field traits
+//!
+//! With `field_traits`, every field with a getter gets a small trait of its own, such as
+//! `HasLogger` for the `logger` field. The trait has an associated type named after the field,
+//! the getter and the mutable getter. When the field is a type parameter, the associated type
+//! keeps the bounds which don't need other generic parameters. The `trait_decl` trait extends all
+//! the field traits instead of declaring the getters itself, so a function can ask for just the
+//! fields it uses. Unlike the `haz` integration, this works when several fields have the same
+//! type. Unnamed fields need a `#[degeneric(name = "...")]`. `field_traits` can't be combined with
+//! `split_mut` or `group_supertraits`.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", field_traits)]
+//! pub struct Container<Logger: Debug, Client> {
+//!     logger: Logger,
+//!     client: Client,
+//!     backup_client: Client,
+//! }
+//!
+//! fn log(deps: &impl HasLogger) -> String {
+//!     format!("{:?}", deps.logger())
+//! }
+//!
+//! fn handle(c: &mut impl ContainerTrait<Client = u16>) -> String {
+//!     *c.backup_client_mut() += 1;
+//!     format!("{} {} {}", log(c), c.client(), c.backup_client())
+//! }
+//!
+//! let mut c = Container { logger: "stdout", client: 80, backup_client: 8080 };
+//! assert_eq!(handle(&mut c), "\"stdout\" 80 8081");
+//! ```
+//!
+//! The field traits are declared next to the struct, so two containers in the same module can't
+//! both declare `HasLogger`. Point the field of the second container at the existing trait with
+//! `#[degeneric(field_trait = "HasLogger")]` and it only implements it. The field needs the same
+//! name, and its type has to satisfy the bounds of the existing associated type.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ServerTrait", field_traits)]
+//! pub struct Server<Logger> {
+//!     logger: Logger,
+//!     port: u16,
+//! }
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait WorkerTrait", field_traits)]
+//! pub struct Worker<Logger> {
+//!     #[degeneric(field_trait = "HasLogger")]
+//!     logger: Logger,
+//!     threads: u8,
+//! }
+//!
+//! fn log(deps: &impl HasLogger<Logger = &'static str>) -> &'static str {
+//!     deps.logger()
+//! }
+//!
+//! assert_eq!(log(&Server { logger: "server", port: 80 }), "server");
+//! assert_eq!(log(&Worker { logger: "worker", threads: 4 }), "worker");
+//! ```
+//!
+//! # Flattening nested containers
+//!
+//! Containers can be composed of other containers. Mark the inner container `flattenable` and the
+//! field holding it `#[degeneric(flatten(trait = "DbDepsTrait"))]`. Every implementor of the
+//! outer trait then implements the inner trait by forwarding to the field, so generic code can
+//! call the getters of the inner container directly on the outer one. The associated types of
+//! both traits are tied together: for `impl AppTrait<Connection = u8>`, the inner `connection`
+//! returns a `&u8`.
+//!
+//! `flattenable` generates a hidden `DbDepsTraitFlatten` helper trait next to the inner trait,
+//! which has to be in scope of the outer struct. Only methods borrowing the container can be
+//! forwarded, so `flattenable` can't be combined with `split_mut`, `mut_parts`, `into_parts`,
+//! `constructor`, `concrete`, `field_traits` or `group_supertraits`. The outer trait can't use
+//! `split_mut` either.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait DbDepsTrait", flattenable)]
+//! pub struct DbDeps<Connection: Debug> {
+//!     connection: Connection,
+//! }
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait AppTrait")]
+//! pub struct App<Connection: Debug, Service> {
+//!     #[degeneric(flatten(trait = "DbDepsTrait"))]
+//!     db: DbDeps<Connection>,
+//!     service: Service,
+//! }
+//!
+//! fn handle(app: &impl AppTrait) -> String {
+//!     format!("{:?}", app.connection())
+//! }
+//!
+//! fn port(app: &impl AppTrait<Connection = u16>) -> u16 {
+//!     *app.connection()
+//! }
+//!
+//! let app = App { db: DbDeps { connection: "postgres" }, service: () };
+//! assert_eq!(handle(&app), "\"postgres\"");
+//! assert_eq!(port(&App { db: DbDeps { connection: 5432 }, service: () }), 5432);
+//! ```
+//!
+//! # Delegating the trait to a wrapper
+//!
+//! With `delegate_macro`, degeneric generates a `macro_rules!` named after the trait, e.g.
+//! `delegate_container_trait!` for `ContainerTrait`. It implements the trait for a wrapper type by
+//! forwarding every associated type, const and method to one of its fields. The generic
+//! parameters of the impl go in square brackets, followed by the wrapper, the field and its type:
+//! `delegate_container_trait!([C] Traced<C> => self.0: C)`. The brackets can be left out when
+//! the wrapper isn't generic. In `split_mut` mode, the mutable trait is implemented as well.
+//!
+//! The macro can be used after the struct in the same module, and the trait has to be in scope
+//! where it's used. `delegate_macro` can't be combined with `mut_parts`, `constructor`,
+//! `concrete`, `field_traits`, `group_supertraits` or flattened fields.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", delegate_macro)]
+//! pub struct Container<Logger: Debug> {
+//!     logger: Logger,
+//! }
+//!
+//! struct Traced<C>(C);
+//!
+//! delegate_container_trait!([C] Traced<C> => self.0: C);
+//!
+//! fn log(c: &impl ContainerTrait) -> String {
+//!     format!("{:?}", c.logger())
+//! }
+//!
+//! let traced = Traced(Container { logger: "stdout" });
+//! assert_eq!(log(&traced), "\"stdout\"");
+//! ```
+//!
+//! # Looking up fields by type at runtime
+//!
+//! With `any_lookup`, the trait gets `get_any(&self, id: TypeId) -> Option<&dyn Any>` and the
+//! typed `get::<T>()`, plus the `get_any_mut` and `get_mut` variants. They return the first field
+//! of the requested type. Only fields whose types are known to be `'static` take part in the
+//! lookup: fields using other lifetimes are skipped, and so are fields using a type parameter
+//! without a `'static` bound. Unsized fields, such as `[u8]`, `str`, `dyn Trait` or a bare
+//! `?Sized` type parameter, can't be borrowed as `&dyn Any` and are skipped as well.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", any_lookup)]
+//! pub struct Container<'a, Logger: Debug + 'static> {
+//!     logger: Logger,
+//!     port: u16,
+//!     // skipped, it borrows for 'a
+//!     name: &'a str,
+//! }
+//!
+//! fn bump_port<'a>(c: &mut impl ContainerTrait<'a>) {
+//!     if let Some(port) = c.get_mut::<u16>() {
+//!         *port += 1;
+//!     }
+//! }
+//!
+//! let mut c = Container { logger: "stdout", port: 8080, name: "app" };
+//! bump_port(&mut c);
+//! assert_eq!(c.get::<u16>(), Some(&8081));
+//! assert_eq!(c.get::<&str>(), Some(&"stdout"));
+//! ```
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::any::TypeId;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait PacketTrait", any_lookup)]
+//! pub struct Packet<Data: ?Sized + 'static> {
+//!     port: u16,
+//!     // skipped, it's unsized for `Packet<[u8]>`
+//!     data: Data,
+//! }
+//!
+//! let packet: &Packet<[u8]> = &Packet { port: 53, data: [1, 2, 3] };
+//! let port = packet.get_any(TypeId::of::<u16>()).and_then(|any| any.downcast_ref::<u16>());
+//! assert_eq!(port, Some(&53));
+//! ```
+//!
+//! # Describing the dependencies
+//!
+//! With `describe`, the trait gets `describe_dependencies()`, which lists the name of every
+//! associated type and every field with a getter together with the name of its concrete type, as
+//! returned by [`core::any::type_name`]. It's handy in startup logs and test assertions. The
+//! exact type names aren't guaranteed to be stable across compiler versions.
+//!
+//! ```
+//! use degeneric_macros::Degeneric;
+//! use std::fmt::Debug;
+//!
+//! #[derive(Degeneric)]
+//! #[degeneric(trait_decl = "pub trait ContainerTrait", describe)]
+//! pub struct Container<Logger: Debug> {
+//!     logger: Logger,
+//!     port: u16,
+//! }
+//!
+//! fn wiring<C: ContainerTrait>(_: &C) -> Vec<(&'static str, &'static str)> {
+//!     C::describe_dependencies()
+//! }
+//!
+//! let c = Container { logger: String::new(), port: 8080 };
+//! assert_eq!(
+//!     wiring(&c),
+//!     [("Logger", "alloc::string::String"), ("logger", "alloc::string::String"), ("port", "u16")],
+//! );
+//! ```
+//!
 //! # Generate getters only for some fields
 //!
 //! The `no_getter` attribute can be used to skip generating a getter.
@@ -516,6 +1539,35 @@ pub fn degeneric(input: TokenStream) -> TokenStream {
     TokenStream::from(tokens)
 }
 
+#[proc_macro_attribute]
+#[proc_macro_error]
+/// Usable only on structs. Collects the type parameters of the struct into a single parameter
+/// implementing the generated types trait.
+///
+/// Example:
+/// ```
+/// use degeneric_macros::bundle;
+///
+/// #[bundle(types = "ContainerTypes")]
+/// struct Container<A: Clone, B> {
+///     a: A,
+///     b: Vec<B>,
+/// }
+///
+/// fn first_b<T: ContainerTypes>(c: &Container<T>) -> Option<&T::B> {
+///     c.b.first()
+/// }
+///
+/// let c: Container<ContainerTypesOf<u8, bool>> = Container { a: 1, b: vec![true] };
+/// assert_eq!(first_b(&c), Some(&true));
+/// ```
+pub fn bundle(args: TokenStream, input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+    let tokens = self::degeneric::process_bundle(args.into(), input)
+        .unwrap_or_else(|err| err.to_compile_error());
+    TokenStream::from(tokens)
+}
+
 #[proc_macro_derive(CloneExt, attributes(clone_ext))]
 #[proc_macro_error]
 /// There might be a situation where you're holding a non-cloneable type inside another type. In